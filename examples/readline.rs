@@ -0,0 +1,20 @@
+use clinput::{App, AppInterface, Error, LogEvent};
+
+type Editor = App<fn(&mut AppInterface<'_>), fn(&LogEvent)>;
+
+fn main() {
+    let mut app = Editor::new();
+    app.history("history.txt".to_owned());
+    let name = match app.readline("name> ") {
+        Ok(name) => name,
+        Err(Error::Interrupted | Error::Eof) => return,
+        Err(err) => panic!("{err}"),
+    };
+    loop {
+        match app.readline(&format!("Delete {name}? [y/n] ")).as_deref() {
+            Ok("y") => break println!("{name} deleted"),
+            Ok("n") | Err(_) => break,
+            Ok(_) => (),
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::fs::{File, OpenOptions};
 use std::io::Write as _;
+use std::process;
 
 use clinput::{App, Key};
 
@@ -18,8 +19,16 @@ fn main() {
     app.on_submit(|interface| {
         writeln!(&mut appender("lines.txt"), "{}", interface.line()).unwrap()
     });
-    app.on(Key::Escape, Box::new(|app| app.exit()));
-    app.log(|err| writeln!(errors, "{err}").unwrap());
+    app.on(Key::ESCAPE, Box::new(|app| app.exit())).unwrap();
+    app.on(
+        Key::ctrl('q'),
+        Box::new(|app| {
+            let quoted = format!("\"{}\"", app.line());
+            app.replace_line(quoted);
+        }),
+    )
+    .unwrap();
+    app.log(|event| writeln!(errors, "[{:?}] {event}", event.level()).unwrap());
     app.history("history.txt".to_owned());
-    app.run();
+    process::exit(app.run().unwrap());
 }
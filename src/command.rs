@@ -0,0 +1,179 @@
+//! Defines the editing commands that can be invoked by name
+
+use std::collections::HashMap;
+
+use crate::key::KeyPress;
+
+/// Built-in editing commands
+///
+/// Every command has a name, the same as readline's one when it exists, which
+/// can be used in key bindings, configuration files and handlers.
+#[non_exhaustive]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+    /// Submit the line
+    AcceptLine,
+    /// Move the cursor one character left
+    BackwardChar,
+    /// Remove the character before the cursor
+    BackwardDeleteChar,
+    /// Kill the text from the start of the line to the cursor
+    BackwardKillLine,
+    /// Kill the word before the cursor
+    BackwardKillWord,
+    /// Move the cursor to the start of the current or previous word
+    BackwardWord,
+    /// Replace the line with the oldest entry of the history
+    BeginningOfHistory,
+    /// Move the cursor to the start of the line, or of its row in a
+    /// multi-line line
+    BeginningOfLine,
+    /// Clear the screen
+    ClearScreen,
+    /// Remove the character under the cursor
+    DeleteChar,
+    /// Move the cursor to the next row of a multi-line line, or replace the
+    /// line with the next entry of the history on the last row
+    DownLineOrHistory,
+    /// Exit if the line is empty, otherwise remove the character under the
+    /// cursor
+    EndOfFile,
+    /// Go back to the line being edited after the history
+    EndOfHistory,
+    /// Move the cursor to the end of the line, or of its row in a multi-line
+    /// line
+    EndOfLine,
+    /// Move the cursor one character right
+    ForwardChar,
+    /// Move the cursor to the end of the current or next word
+    ForwardWord,
+    /// Replace the line with the previous entry of the history starting with
+    /// the text before the cursor
+    HistorySearchBackward,
+    /// Replace the line with the next entry of the history starting with the
+    /// text before the cursor
+    HistorySearchForward,
+    /// Insert a newline at the cursor, to continue the line on a new row
+    InsertNewline,
+    /// Abandon the line and start a new one, or exit if configured so
+    Interrupt,
+    /// Kill the text from the cursor to the end of the line
+    KillLine,
+    /// Kill the text from the cursor to the end of the current or next word
+    KillWord,
+    /// Replace the line with the next entry of the history
+    NextHistory,
+    /// Replace the line with the previous entry of the history
+    PreviousHistory,
+    /// Suspend the process, like Ctrl-Z in a shell
+    Suspend,
+    /// Swap the character before the cursor with the one under the cursor
+    TransposeChars,
+    /// Move the cursor to the previous row of a multi-line line, or replace
+    /// the line with the previous entry of the history on the first row
+    UpLineOrHistory,
+    /// Insert the last killed text at the cursor
+    Yank,
+}
+
+impl Command {
+    /// Names of the commands, as used by readline
+    const NAMES: [(&'static str, Self); 29] = [
+        ("accept-line", Self::AcceptLine),
+        ("backward-char", Self::BackwardChar),
+        ("backward-delete-char", Self::BackwardDeleteChar),
+        ("backward-kill-line", Self::BackwardKillLine),
+        ("backward-kill-word", Self::BackwardKillWord),
+        ("backward-word", Self::BackwardWord),
+        ("beginning-of-history", Self::BeginningOfHistory),
+        ("beginning-of-line", Self::BeginningOfLine),
+        ("clear-screen", Self::ClearScreen),
+        ("delete-char", Self::DeleteChar),
+        ("down-line-or-history", Self::DownLineOrHistory),
+        ("end-of-file", Self::EndOfFile),
+        ("end-of-history", Self::EndOfHistory),
+        ("end-of-line", Self::EndOfLine),
+        ("forward-char", Self::ForwardChar),
+        ("forward-word", Self::ForwardWord),
+        ("history-search-backward", Self::HistorySearchBackward),
+        ("history-search-forward", Self::HistorySearchForward),
+        ("insert-newline", Self::InsertNewline),
+        ("interrupt", Self::Interrupt),
+        ("kill-line", Self::KillLine),
+        ("kill-word", Self::KillWord),
+        ("next-history", Self::NextHistory),
+        ("previous-history", Self::PreviousHistory),
+        ("suspend", Self::Suspend),
+        ("transpose-chars", Self::TransposeChars),
+        ("unix-line-discard", Self::BackwardKillLine),
+        ("up-line-or-history", Self::UpLineOrHistory),
+        ("yank", Self::Yank),
+    ];
+
+    /// Iterates over all the built-in commands with their names
+    ///
+    /// A command can have several names.
+    pub fn all() -> impl Iterator<Item = (&'static str, Self)> {
+        Self::NAMES.into_iter()
+    }
+
+    /// Finds the command with the given name, e.g. `beginning-of-line`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(command_name, _)| command_name.eq_ignore_ascii_case(name))
+            .map(|(_, command)| *command)
+    }
+
+    /// Get the name of the command, e.g. `beginning-of-line`
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        Self::NAMES
+            .iter()
+            .find(|(_, command)| *command == self)
+            .map_or("", |(name, _)| name)
+    }
+}
+
+/// Command found in the registry
+pub enum Named<'registry> {
+    /// Built-in editing command
+    Builtin(Command),
+    /// Command defined by the user
+    Custom(&'registry mut KeyPress),
+}
+
+/// Registry of all the commands that can be invoked by name
+///
+/// It contains the built-in commands and the ones defined by the user.
+#[derive(Default)]
+pub struct Registry(HashMap<String, KeyPress>);
+
+impl Registry {
+    /// Checks if a command with the given name exists
+    pub fn contains(&self, name: &str) -> bool {
+        Command::from_name(name).is_some() || self.0.contains_key(name)
+    }
+
+    /// Defines a new command, or replaces a command defined by the user
+    ///
+    /// The built-in commands can't be replaced, as they have priority.
+    pub fn define(&mut self, name: String, action: KeyPress) {
+        self.0.insert(name, action);
+    }
+
+    /// Finds the command with the given name
+    pub fn get_mut(&mut self, name: &str) -> Option<Named<'_>> {
+        Command::from_name(name)
+            .map(Named::Builtin)
+            .or_else(|| self.0.get_mut(name).map(Named::Custom))
+    }
+
+    /// Iterates over the names of all the commands
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        Command::all()
+            .map(|(name, _)| name)
+            .chain(self.0.keys().map(String::as_str))
+    }
+}
@@ -0,0 +1,126 @@
+//! Defines the diagnostics reported by the runner to the log
+
+use core::fmt;
+
+use crate::error::Error;
+use crate::key::{DisplaySequence, Key};
+
+/// Target of the diagnostics forwarded to the `log` and `tracing` facades
+#[cfg(any(feature = "log", feature = "tracing"))]
+const TARGET: &str = "clinput";
+
+/// Importance of a [`LogEvent`]
+#[non_exhaustive]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Level {
+    /// Details about the keys pressed by the end user
+    Debug,
+    /// An operation failed
+    Error,
+    /// Something happened in the runner
+    Info,
+    /// Something went wrong because of the input of the end user
+    Warn,
+}
+
+/// Diagnostic reported by the runner to the log
+///
+/// See [`App::log`](crate::App::log).
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum LogEvent {
+    /// An error occurred, but the input goes on
+    Error(Error),
+    /// The history in use was loaded from its storage
+    HistoryLoaded {
+        /// Name of the history
+        name: String,
+        /// Number of entries loaded
+        len: usize,
+    },
+    /// A character was inserted into the line
+    Inserted(char),
+    /// A key was pressed
+    KeyPressed(Key),
+    /// The keys of a sequence were pressed too slowly, and handled as if they
+    /// weren't bound
+    SequenceTimedOut(Vec<Key>),
+    /// The runner started
+    Started,
+    /// A key that isn't bound, and isn't a character, was pressed
+    Unbound(Key),
+}
+
+impl LogEvent {
+    /// Forwards the event to the `log` facade, with its fields as key-values
+    #[cfg(feature = "log")]
+    pub(crate) fn emit_log(&self) {
+        match self {
+            Self::Error(err) if self.level() == Level::Warn =>
+                log::warn!(target: TARGET, error:% = err; "invalid input"),
+            Self::Error(err) => log::error!(target: TARGET, error:% = err; "error"),
+            Self::HistoryLoaded { name, len } =>
+                log::info!(target: TARGET, name:% = name, len; "history loaded"),
+            Self::Inserted(ch) => log::debug!(target: TARGET, ch:% = ch; "character inserted"),
+            Self::KeyPressed(key) => log::debug!(target: TARGET, key:% = key; "key pressed"),
+            Self::SequenceTimedOut(keys) => log::info!(
+                target: TARGET,
+                keys:% = DisplaySequence(keys);
+                "sequence timed out"
+            ),
+            Self::Started => log::info!(target: TARGET, "runner started"),
+            Self::Unbound(key) => log::debug!(target: TARGET, key:% = key; "unbound key pressed"),
+        }
+    }
+
+    /// Forwards the event to the `tracing` facade, with its fields
+    #[cfg(feature = "tracing")]
+    pub(crate) fn emit_tracing(&self) {
+        match self {
+            Self::Error(err) if self.level() == Level::Warn =>
+                tracing::warn!(target: TARGET, error = %err, "invalid input"),
+            Self::Error(err) => tracing::error!(target: TARGET, error = %err, "error"),
+            Self::HistoryLoaded { name, len } =>
+                tracing::info!(target: TARGET, name = %name, len, "history loaded"),
+            Self::Inserted(ch) => tracing::debug!(target: TARGET, ch = %ch, "character inserted"),
+            Self::KeyPressed(key) => tracing::debug!(target: TARGET, key = %key, "key pressed"),
+            Self::SequenceTimedOut(keys) => tracing::info!(
+                target: TARGET,
+                keys = %DisplaySequence(keys),
+                "sequence timed out"
+            ),
+            Self::Started => tracing::info!(target: TARGET, "runner started"),
+            Self::Unbound(key) =>
+                tracing::debug!(target: TARGET, key = %key, "unbound key pressed"),
+        }
+    }
+
+    /// Get the importance of the event
+    #[must_use]
+    pub const fn level(&self) -> Level {
+        match self {
+            Self::Error(
+                Error::Expansion(_) | Error::RecursiveCommand(_) | Error::UnknownCommand(_),
+            ) => Level::Warn,
+            Self::Error(_) => Level::Error,
+            Self::HistoryLoaded { .. } | Self::SequenceTimedOut(_) | Self::Started => Level::Info,
+            Self::Inserted(_) | Self::KeyPressed(_) | Self::Unbound(_) => Level::Debug,
+        }
+    }
+}
+
+impl fmt::Display for LogEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error(err) => err.fmt(f),
+            Self::HistoryLoaded { name, len } =>
+                write!(f, "history {name} loaded with {len} entries"),
+            Self::Inserted(ch) => write!(f, "inserted `{ch}`"),
+            Self::KeyPressed(key) => write!(f, "pressed {key}"),
+            Self::SequenceTimedOut(keys) =>
+                write!(f, "sequence {} timed out", DisplaySequence(keys)),
+            Self::Started => "runner started".fmt(f),
+            Self::Unbound(key) => write!(f, "{key} isn't bound"),
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Defines the errors that can occur while reading the lines
+
+use core::{error, fmt};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::History;
+use crate::history::expansion::ExpansionError;
+
+/// Error that occurred while reading the lines
+///
+/// [`App::run`](crate::App::run) and [`App::readline`](crate::App::readline)
+/// return the errors after which the input can't be read anymore. The other
+/// ones are reported to the log, see [`App::log`](crate::App::log), and the
+/// input goes on.
+#[non_exhaustive]
+#[derive(Debug)]
+#[expect(clippy::error_impl_error, reason = "the crate's error")]
+pub enum Error {
+    /// The input was cancelled by the future given to the asynchronous runner
+    Cancelled,
+    /// Invalid line in a configuration file
+    Config {
+        /// Number of the line, starting from 1
+        line: usize,
+        /// Description of the error
+        message: String,
+    },
+    /// Failed to read a configuration file
+    ConfigRead {
+        /// Path of the configuration file
+        path: PathBuf,
+        /// Error that occurred while reading the file
+        source: io::Error,
+    },
+    /// End of the input, e.g. Ctrl-D was pressed on an empty line
+    Eof,
+    /// Failed to read an event from the terminal
+    EventRead(io::Error),
+    /// Failed to expand the history references of the submitted line
+    Expansion(ExpansionError),
+    /// A handler panicked, with the given message
+    HandlerPanic(String),
+    /// Failed to read or write a history
+    History {
+        /// Path of the file of the history, if it is stored in a file
+        path: Option<PathBuf>,
+        /// Error that occurred while accessing the storage
+        source: io::Error,
+    },
+    /// Line abandoned, e.g. Ctrl-C was pressed
+    Interrupted,
+    /// A command defined by the user was invoked while it was running, by
+    /// itself or through other commands
+    RecursiveCommand(String),
+    /// Failed to set up or write to the terminal
+    Terminal(io::Error),
+    /// A command was invoked with a name that doesn't exist
+    UnknownCommand(String),
+}
+
+impl Error {
+    /// Creates an error for a history that couldn't be read or written
+    pub(crate) fn history(history: &History, source: io::Error) -> Self {
+        Self::History { path: history.path().map(Path::to_path_buf), source }
+    }
+
+    /// Checks if the input can't be read anymore after this error
+    pub(crate) const fn is_fatal(&self) -> bool {
+        matches!(
+            self,
+            Self::Cancelled
+                | Self::Eof
+                | Self::EventRead(_)
+                | Self::HandlerPanic(_)
+                | Self::Interrupted
+        )
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cancelled => "cancelled".fmt(f),
+            Self::Config { line, message } => write!(f, "inputrc:{line}: {message}"),
+            Self::ConfigRead { path, source } => write!(f, "{}: {source}", path.display()),
+            Self::Eof => "end of input".fmt(f),
+            Self::EventRead(err) => write!(f, "failed to read the terminal: {err}"),
+            Self::Expansion(err) => err.fmt(f),
+            Self::HandlerPanic(message) => write!(f, "handler panicked: {message}"),
+            Self::History { path: Some(path), source } =>
+                write!(f, "history {}: {source}", path.display()),
+            Self::History { path: None, source } => write!(f, "history: {source}"),
+            Self::Interrupted => "interrupted".fmt(f),
+            Self::RecursiveCommand(name) => write!(f, "command `{name}` invoked while running"),
+            Self::Terminal(err) => write!(f, "terminal: {err}"),
+            Self::UnknownCommand(name) => write!(f, "unknown command `{name}`"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::ConfigRead { source, .. } | Self::History { source, .. } => Some(source),
+            Self::EventRead(err) | Self::Terminal(err) => Some(err),
+            Self::Expansion(err) => Some(err),
+            Self::Cancelled
+            | Self::Config { .. }
+            | Self::Eof
+            | Self::HandlerPanic(_)
+            | Self::Interrupted
+            | Self::RecursiveCommand(_)
+            | Self::UnknownCommand(_) => None,
+        }
+    }
+}
+
+impl From<ExpansionError> for Error {
+    fn from(err: ExpansionError) -> Self {
+        Self::Expansion(err)
+    }
+}
+
+impl From<io::Error> for Error {
+    /// Converts an I/O error into a [`Error::Terminal`], as the terminal is
+    /// the only I/O of the runner that isn't attached to a file
+    fn from(err: io::Error) -> Self {
+        Self::Terminal(err)
+    }
+}
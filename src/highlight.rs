@@ -0,0 +1,406 @@
+//! Highlights the line being edited with styled spans
+
+use core::iter::Peekable;
+use core::ops::Range;
+use core::str::CharIndices;
+
+use crossterm::style::{ContentStyle, Stylize as _};
+
+/// Pairs of brackets, opening then closing
+pub const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// Highlights the line being edited
+///
+/// The spans returned for a line are drawn with their styles, the last one
+/// prevailing where they overlap. The position of the cursor is only used to
+/// measure the line, so it stays in place whatever the styles. The line is
+/// highlighted only if the colors are enabled, see
+/// [`App::color`](crate::App::color).
+///
+/// It is implemented for the closures taking the line and the position of
+/// the cursor, and for the pairs of highlighters, e.g. to highlight both the
+/// tokens and the matching brackets.
+pub trait Highlighter {
+    /// Computes the styled spans of the line, given the position of the
+    /// cursor in bytes
+    fn highlight(&self, line: &str, cursor: usize) -> Vec<Span>;
+}
+
+impl<F: Fn(&str, usize) -> Vec<Span>> Highlighter for F {
+    fn highlight(&self, line: &str, cursor: usize) -> Vec<Span> {
+        self(line, cursor)
+    }
+}
+
+impl<A: Highlighter, B: Highlighter> Highlighter for (A, B) {
+    /// Highlights the line with both highlighters, the spans of the second
+    /// one prevailing
+    fn highlight(&self, line: &str, cursor: usize) -> Vec<Span> {
+        let (first, second) = self;
+        let mut spans = first.highlight(line, cursor);
+        spans.extend(second.highlight(line, cursor));
+        spans
+    }
+}
+
+/// Highlights the bracket at the cursor and the one matching it
+///
+/// The bracket under the cursor is used, or else the one before it, e.g. when
+/// a closing bracket was just typed. Nothing is highlighted if the bracket
+/// isn't matched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MatchingBrackets {
+    /// Style of the two brackets
+    style: ContentStyle,
+}
+
+impl MatchingBrackets {
+    /// Creates a highlighter drawing the matching brackets with the given
+    /// style
+    #[must_use]
+    pub const fn new(style: ContentStyle) -> Self {
+        Self { style }
+    }
+}
+
+impl Default for MatchingBrackets {
+    /// Draws the matching brackets in bold and underlined
+    fn default() -> Self {
+        Self::new(ContentStyle::new().bold().underlined())
+    }
+}
+
+impl Highlighter for MatchingBrackets {
+    fn highlight(&self, line: &str, cursor: usize) -> Vec<Span> {
+        let before = line
+            .get(..cursor)
+            .and_then(|text| text.char_indices().next_back())
+            .map(|(index, _)| index);
+        [Some(cursor), before]
+            .into_iter()
+            .flatten()
+            .find_map(|position| Some((position, matching_bracket(line, position)?)))
+            .map_or_else(Vec::new, |(bracket, matching)| {
+                vec![
+                    Span::new(bracket..bracket.saturating_add(1), self.style),
+                    Span::new(matching..matching.saturating_add(1), self.style),
+                ]
+            })
+    }
+}
+
+/// Style applied to a part of the line
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    /// Range of the styled text in the line, in bytes
+    pub range: Range<usize>,
+    /// Style of the text
+    pub style: ContentStyle,
+}
+
+impl Span {
+    /// Creates a span styling the given range of the line, in bytes
+    #[must_use]
+    pub const fn new(range: Range<usize>, style: ContentStyle) -> Self {
+        Self { range, style }
+    }
+}
+
+/// Highlights the keywords, strings and numbers of the line
+///
+/// Strings are delimited by single or double quotes, which can be escaped
+/// inside them with a backslash. Numbers are the words starting with a digit,
+/// e.g. `42`, `1.5` or `0x2A`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tokens {
+    /// Whether the keywords are matched regardless of their case
+    ignore_case: bool,
+    /// Style of the keywords
+    keyword_style: ContentStyle,
+    /// Words highlighted as keywords
+    keywords: Vec<String>,
+    /// Style of the numbers
+    number_style: ContentStyle,
+    /// Style of the strings
+    string_style: ContentStyle,
+}
+
+impl Tokens {
+    /// Matches the keywords regardless of their case, e.g. for SQL
+    pub const fn ignore_case(&mut self, ignore: bool) {
+        self.ignore_case = ignore;
+    }
+
+    /// Checks if a word is one of the keywords
+    fn is_keyword(&self, word: &str) -> bool {
+        self.keywords.iter().any(|keyword| {
+            if self.ignore_case {
+                keyword.eq_ignore_ascii_case(word)
+            } else {
+                keyword == word
+            }
+        })
+    }
+
+    /// Sets the style of the keywords, bold blue by default
+    pub const fn keyword_style(&mut self, style: ContentStyle) {
+        self.keyword_style = style;
+    }
+
+    /// Creates a highlighter for the given keywords
+    #[must_use]
+    pub fn new(keywords: &[&str]) -> Self {
+        Self {
+            ignore_case: false,
+            keyword_style: ContentStyle::new().bold().blue(),
+            keywords: keywords.iter().map(|&keyword| keyword.to_owned()).collect(),
+            number_style: ContentStyle::new().yellow(),
+            string_style: ContentStyle::new().green(),
+        }
+    }
+
+    /// Sets the style of the numbers, yellow by default
+    pub const fn number_style(&mut self, style: ContentStyle) {
+        self.number_style = style;
+    }
+
+    /// Sets the style of the strings, green by default
+    pub const fn string_style(&mut self, style: ContentStyle) {
+        self.string_style = style;
+    }
+}
+
+impl Highlighter for Tokens {
+    fn highlight(&self, line: &str, _cursor: usize) -> Vec<Span> {
+        let mut spans = vec![];
+        let mut chars = line.char_indices().peekable();
+        while let Some((start, ch)) = chars.next() {
+            if ch == '"' || ch == '\'' {
+                let end =
+                    string_end(&mut chars, ch).map_or(line.len(), |index| index.saturating_add(1));
+                spans.push(Span::new(start..end, self.string_style));
+            } else if is_word(ch) {
+                let number = ch.is_ascii_digit();
+                let end = word_end(&mut chars, number, line.len());
+                if number {
+                    spans.push(Span::new(start..end, self.number_style));
+                } else if self.is_keyword(line.get(start..end).unwrap_or_default()) {
+                    spans.push(Span::new(start..end, self.keyword_style));
+                }
+            }
+        }
+        spans
+    }
+}
+
+/// Checks if a character is part of a word
+fn is_word(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/// Finds the bracket matching the one at the given position, in bytes
+fn matching_bracket(line: &str, position: usize) -> Option<usize> {
+    let bracket = line.get(position..)?.chars().next()?;
+    if let Some(&(open, close)) = BRACKETS.iter().find(|(open, _)| *open == bracket) {
+        let after = line.get(position..)?.char_indices();
+        let offset = nesting_end(after, open, close)?;
+        return Some(position.saturating_add(offset));
+    }
+    let &(open, close) = BRACKETS.iter().find(|(_, close)| *close == bracket)?;
+    nesting_end(line.get(..=position)?.char_indices().rev(), close, open)
+}
+
+/// Finds the bracket closing the one starting the characters
+///
+/// The characters can be reversed to find the opening bracket, with the
+/// brackets swapped.
+fn nesting_end(
+    chars: impl Iterator<Item = (usize, char)>,
+    open: char,
+    close: char,
+) -> Option<usize> {
+    let mut depth = 0usize;
+    for (index, ch) in chars {
+        if ch == open {
+            depth = depth.saturating_add(1);
+        } else if ch == close {
+            depth = depth.saturating_sub(1);
+            if depth == 0 {
+                return Some(index);
+            }
+        }
+    }
+    None
+}
+
+/// Draws the line with the styles of the spans
+pub fn paint(line: &str, spans: &[Span]) -> String {
+    if line.is_empty() {
+        return String::new();
+    }
+    let style_at = |index: usize| {
+        spans
+            .iter()
+            .rev()
+            .find(|span| span.range.contains(&index))
+            .map(|span| span.style)
+    };
+    let mut painted = String::new();
+    let mut start = 0;
+    let mut current = style_at(0);
+    let boundaries = line.char_indices().map(|(index, _)| index).skip(1);
+    for index in boundaries.chain([line.len()]) {
+        let style = style_at(index);
+        if style != current || index == line.len() {
+            let text = line.get(start..index).unwrap_or_default();
+            match current {
+                Some(applied) => painted.push_str(&applied.apply(text).to_string()),
+                None => painted.push_str(text),
+            }
+            start = index;
+            current = style;
+        }
+    }
+    painted
+}
+
+/// Moves the characters past the quote closing a string, and returns its
+/// position
+///
+/// The quotes escaped with a backslash are skipped. Returns `None` if the
+/// string isn't closed.
+pub fn string_end<I>(chars: &mut I, quote: char) -> Option<usize>
+where
+    I: Iterator<Item = (usize, char)>,
+{
+    let mut escaped = false;
+    chars
+        .find(|&(_, next)| {
+            let closing = next == quote && !escaped;
+            escaped = !escaped && next == '\\';
+            closing
+        })
+        .map(|(index, _)| index)
+}
+
+/// Moves the characters to the end of a word, and returns its position
+///
+/// The numbers can contain dots.
+fn word_end(chars: &mut Peekable<CharIndices<'_>>, number: bool, len: usize) -> usize {
+    while chars
+        .next_if(|&(_, next)| is_word(next) || (number && next == '.'))
+        .is_some()
+    {}
+    chars.peek().map_or(len, |&(index, _)| index)
+}
+
+#[cfg(test)]
+#[expect(clippy::inline_modules, reason = "tests")]
+mod tests {
+    use super::*;
+
+    /// Styles the text as [`paint`] does
+    fn styled(style: ContentStyle, text: &str) -> String {
+        style.apply(text).to_string()
+    }
+
+    #[test]
+    fn brackets() {
+        let brackets = MatchingBrackets::new(ContentStyle::new().red());
+        let ranges = |line: &str, cursor: usize| -> Vec<Range<usize>> {
+            brackets
+                .highlight(line, cursor)
+                .into_iter()
+                .map(|span| span.range)
+                .collect()
+        };
+        assert_eq!(ranges("(a[b]c)", 0), [0..1, 6..7]);
+        assert_eq!(ranges("(a[b]c)", 5), [4..5, 2..3]);
+        assert_eq!(ranges("(a[b]c)", 7), [6..7, 0..1]);
+        assert_eq!(ranges("(a[b]c)", 1), [0..1, 6..7]);
+        assert!(ranges("((a)", 0).is_empty());
+        assert!(ranges("a)", 2).is_empty());
+        assert!(ranges("abc", 1).is_empty());
+        assert_eq!(ranges("(\u{e9})", 3), [3..4, 0..1]);
+        assert_eq!(ranges("(\u{e9})", 4), [3..4, 0..1]);
+    }
+
+    #[test]
+    fn painted() {
+        let red = ContentStyle::new().red();
+        let blue = ContentStyle::new().blue();
+        assert_eq!(paint("", &[Span::new(0..1, red)]), "");
+        assert_eq!(paint("abc", &[]), "abc");
+        assert_eq!(
+            paint("\u{e9}ab", &[Span::new(0..2, red)]),
+            format!("{}ab", styled(red, "\u{e9}"))
+        );
+        let spans = [Span::new(0..3, red), Span::new(1..2, blue)];
+        let painted = paint("abcd", &spans);
+        let expected = [
+            styled(red, "a"),
+            styled(blue, "b"),
+            styled(red, "c"),
+            "d".to_owned(),
+        ];
+        assert_eq!(painted, expected.concat());
+    }
+
+    #[test]
+    fn pair() {
+        let red = ContentStyle::new().red();
+        let blue = ContentStyle::new().blue();
+        let first = move |_: &str, _: usize| vec![Span::new(0..3, red)];
+        let second = move |_: &str, _: usize| vec![Span::new(1..2, blue)];
+        let spans = (first, second).highlight("abc", 0);
+        assert_eq!(spans, [Span::new(0..3, red), Span::new(1..2, blue)]);
+        let painted = paint("abc", &spans);
+        assert_eq!(painted, [styled(red, "a"), styled(blue, "b"), styled(red, "c")].concat());
+    }
+
+    #[test]
+    fn strings() {
+        let mut chars = r#"a\"b"c"#.char_indices();
+        assert_eq!(string_end(&mut chars, '"'), Some(4));
+        assert_eq!(chars.next(), Some((5, 'c')));
+        assert_eq!(string_end(&mut r#"a\\"b"#.char_indices(), '"'), Some(3));
+        assert_eq!(string_end(&mut "a'b".char_indices(), '"'), None);
+    }
+
+    #[test]
+    fn tokens() {
+        let mut highlighter = Tokens::new(&["if", "select"]);
+        let ranges = |tokens: &Tokens, line: &str| -> Vec<Range<usize>> {
+            tokens
+                .highlight(line, 0)
+                .into_iter()
+                .map(|span| span.range)
+                .collect()
+        };
+        let line = r"if x == 'it\'s' 3.14 1.2.3";
+        assert_eq!(ranges(&highlighter, line), [0..2, 8..15, 16..20, 21..26]);
+        let spans = highlighter.highlight("if 'a", 0);
+        assert_eq!(
+            spans,
+            [
+                Span::new(0..2, ContentStyle::new().bold().blue()),
+                Span::new(3..5, ContentStyle::new().green())
+            ]
+        );
+        assert!(ranges(&highlighter, "SELECT iffy iF").is_empty());
+        highlighter.ignore_case(true);
+        assert_eq!(ranges(&highlighter, "SELECT iffy iF"), [0..6, 12..14]);
+        assert_eq!(ranges(&highlighter, "\u{e9} '\u{fc}' 2"), [3..7, 8..9]);
+    }
+
+    #[test]
+    fn words() {
+        let mut number = "1.5.x y".char_indices().peekable();
+        assert_eq!(word_end(&mut number, true, 7), 5);
+        let mut word = "x1.5".char_indices().peekable();
+        assert_eq!(word_end(&mut word, false, 4), 2);
+        let mut end = "42".char_indices().peekable();
+        assert_eq!(word_end(&mut end, true, 2), 2);
+    }
+}
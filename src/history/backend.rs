@@ -0,0 +1,243 @@
+//! Defines where the history is stored
+
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead as _, BufReader, Read as _, Seek as _, Write as _};
+use std::path::{Path, PathBuf};
+
+use crate::IoResult;
+
+/// First line of the history files in which the entries are escaped
+pub(super) const HEADER: &str = "#clinput-history escaped";
+
+/// Storage of the submitted lines, used to keep the history between runs
+///
+/// Implement this trait to store the history somewhere else than in a file,
+/// for instance in a database.
+pub trait HistoryBackend {
+    /// Stores a newly submitted line
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the line couldn't be stored.
+    fn append(&mut self, line: &str) -> IoResult;
+
+    /// Loads all the stored lines, from the oldest to the most recent
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the storage couldn't be read.
+    fn load(&mut self) -> IoResult<Vec<String>>;
+
+    /// Get the path of the file in which the lines are stored, if any
+    ///
+    /// It is used to report the errors.
+    fn path(&self) -> Option<&Path> {
+        None
+    }
+
+    /// Replaces all the stored lines by the given ones
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the storage couldn't be rewritten.
+    fn rewrite(&mut self, lines: &[String]) -> IoResult;
+
+    /// Makes sure all the changes are persisted
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the changes couldn't be persisted.
+    fn sync(&mut self) -> IoResult {
+        Ok(())
+    }
+}
+
+/// Stores the history in a file, one line per entry
+///
+/// This is the same principle as the `.bash_history` file. The new files
+/// start with a header line, after which the newlines of the multi-line
+/// entries are written as `\n` and the backslashes as `\\`. The files without
+/// the header, written by the previous versions, are read with one raw entry
+/// per line, and converted the first time they are written.
+pub struct FileBackend {
+    /// Whether the entries are escaped after the header, or stored raw
+    escaped: bool,
+    /// File in which the lines are stored
+    file: File,
+    /// Path of the file
+    path: PathBuf,
+}
+
+impl FileBackend {
+    /// Opens the storage file, creating it if it doesn't exist
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file couldn't be opened or created.
+    pub fn open<P>(path: P) -> IoResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .read(true)
+            .open(&path)?;
+        let escaped = if file.metadata()?.len() == 0 {
+            writeln!(file, "{HEADER}")?;
+            true
+        } else {
+            let mut first = String::new();
+            BufReader::new(&file).read_line(&mut first)?;
+            first.trim_end() == HEADER
+        };
+        Ok(Self { escaped, file, path: path.as_ref().to_path_buf() })
+    }
+}
+
+impl HistoryBackend for FileBackend {
+    fn append(&mut self, line: &str) -> IoResult {
+        if !self.escaped {
+            let lines = self.load()?;
+            self.rewrite(&lines)?;
+        }
+        writeln!(self.file, "{}", encode(line))
+    }
+
+    fn load(&mut self) -> IoResult<Vec<String>> {
+        let mut ancient_history = String::new();
+        self.file.rewind()?;
+        self.file.read_to_string(&mut ancient_history)?;
+        let mut lines = ancient_history.lines().peekable();
+        self.escaped = lines.next_if_eq(&HEADER).is_some();
+        Ok(if self.escaped {
+            lines.map(decode).collect()
+        } else {
+            lines.map(Into::into).collect()
+        })
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+
+    fn rewrite(&mut self, lines: &[String]) -> IoResult {
+        self.file.set_len(0)?;
+        writeln!(self.file, "{HEADER}")?;
+        self.escaped = true;
+        for line in lines {
+            writeln!(self.file, "{}", encode(line))?;
+        }
+        Ok(())
+    }
+
+    fn sync(&mut self) -> IoResult {
+        self.file.sync_all()
+    }
+}
+
+/// Stores the history in memory
+///
+/// The clones of a [`MemoryBackend`] share the same storage, so one clone can
+/// be given to the [`History`](super::History) and another one kept to inspect
+/// what was stored, which is useful for tests.
+#[derive(Default, Clone)]
+pub struct MemoryBackend {
+    /// Stored lines
+    lines: Rc<RefCell<Vec<String>>>,
+}
+
+impl MemoryBackend {
+    /// Returns a copy of the stored lines
+    #[must_use]
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.borrow().clone()
+    }
+
+    /// Creates a [`MemoryBackend`] already containing some lines
+    #[must_use]
+    pub fn new(lines: Vec<String>) -> Self {
+        Self { lines: Rc::new(RefCell::new(lines)) }
+    }
+}
+
+impl HistoryBackend for MemoryBackend {
+    fn append(&mut self, line: &str) -> IoResult {
+        self.lines.borrow_mut().push(line.to_owned());
+        Ok(())
+    }
+
+    fn load(&mut self) -> IoResult<Vec<String>> {
+        Ok(self.lines())
+    }
+
+    fn rewrite(&mut self, lines: &[String]) -> IoResult {
+        lines.clone_into(&mut self.lines.borrow_mut());
+        Ok(())
+    }
+}
+
+/// Decodes an entry written with [`encode`]
+fn decode(line: &str) -> String {
+    let mut decoded = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            decoded.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => decoded.push('\n'),
+            Some(escaped) => decoded.push(escaped),
+            None => decoded.push(ch),
+        }
+    }
+    decoded
+}
+
+/// Encodes an entry on a single line, escaping its newlines and backslashes
+pub(super) fn encode(line: &str) -> String {
+    line.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+#[expect(clippy::inline_modules, clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::{FileBackend, HEADER, HistoryBackend as _};
+
+    #[test]
+    fn file_round_trip() {
+        let path = env::temp_dir().join(format!("clinput-history-{}.txt", process::id()));
+        let lines = ["select *\nfrom users;", r"C:\new", "", "plain"].map(String::from);
+        let mut backend = FileBackend::open(&path).unwrap();
+        backend.rewrite(&lines[..2]).unwrap();
+        for line in &lines[2..] {
+            backend.append(line).unwrap();
+        }
+        let stored = fs::read_to_string(&path).unwrap();
+        let expected = "select *\\nfrom users;\nC:\\\\new\n\nplain\n";
+        assert_eq!(stored, format!("{HEADER}\n{expected}"));
+        assert_eq!(FileBackend::open(&path).unwrap().load().unwrap(), lines);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn old_format() {
+        let path = env::temp_dir().join(format!("clinput-old-history-{}.txt", process::id()));
+        fs::write(&path, "C:\\new\nprintf \"a\\nb\"\n\\\\\n").unwrap();
+        let old = [r"C:\new", r#"printf "a\nb""#, r"\\"].map(String::from);
+        let mut backend = FileBackend::open(&path).unwrap();
+        assert_eq!(backend.load().unwrap(), old);
+        backend.append("two\nrows").unwrap();
+        let stored = fs::read_to_string(&path).unwrap();
+        assert!(stored.starts_with(HEADER), "{stored}");
+        let mut expected = old.to_vec();
+        expected.push("two\nrows".to_owned());
+        assert_eq!(FileBackend::open(&path).unwrap().load().unwrap(), expected);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,296 @@
+//! Bash-style expansion of the history references inside a line
+//!
+//! The supported syntax is:
+//!
+//! - `!!`: the previous line;
+//! - `!n`: the line number `n`, starting from 1;
+//! - `!-n`: the `n`-th previous line;
+//! - `!prefix`: the most recent line starting with `prefix`;
+//! - `!?pattern?`: the most recent line containing `pattern`;
+//! - `!$`, `!^` and `!*`: the last word, the first argument and all the
+//!   arguments of the previous line;
+//! - `:n`, `:$`, `:^` and `:*` after a reference to select words in the line;
+//! - `^old^new`: the previous line with `old` replaced by `new`.
+
+use core::error::Error;
+use core::fmt;
+
+use super::History;
+
+/// Error that occurred while expanding a line
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum ExpansionError {
+    /// The word designator doesn't match any word of the line
+    BadWordSpecifier(String),
+    /// No line in the history matches the reference
+    EventNotFound(String),
+    /// The pattern of a quick substitution wasn't found
+    SubstitutionFailed,
+}
+
+impl fmt::Display for ExpansionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadWordSpecifier(word) => write!(f, "{word}: bad word specifier"),
+            Self::EventNotFound(event) => write!(f, "{event}: event not found"),
+            Self::SubstitutionFailed => "substitution failed".fmt(f),
+        }
+    }
+}
+
+impl Error for ExpansionError {}
+
+/// Words selected in a line
+enum Designator {
+    /// All the words except the command
+    Arguments,
+    /// The word at the given index
+    Index(usize),
+    /// The last word
+    Last,
+}
+
+impl Designator {
+    /// Parses a designator at the start of `rest`
+    ///
+    /// Returns the designator and its size in bytes.
+    fn parse(rest: &str) -> Option<(Self, usize)> {
+        let designator = match rest.chars().next()? {
+            '$' => Self::Last,
+            '^' => Self::Index(1),
+            '*' => Self::Arguments,
+            _ => {
+                let size = digits_len(rest);
+                return Some((Self::Index(rest.get(..size)?.parse().ok()?), size));
+            }
+        };
+        Some((designator, 1))
+    }
+
+    /// Selects the designated words in the line
+    fn select(&self, line: &str) -> Option<String> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        match self {
+            Self::Arguments => Some(words.get(1..).unwrap_or_default().join(" ")),
+            Self::Index(index) => words.get(*index).map(|word| (*word).to_owned()),
+            Self::Last => words.last().map(|word| (*word).to_owned()),
+        }
+    }
+}
+
+/// Returns the size in bytes of the ASCII digits at the start of `text`
+fn digits_len(text: &str) -> usize {
+    text.find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or(text.len())
+}
+
+/// Expands all the history references in `line`
+///
+/// Returns `None` if the line doesn't contain any reference.
+pub fn expand(history: &History, line: &str) -> Result<Option<String>, ExpansionError> {
+    if let Some(substitution) = line.strip_prefix('^') {
+        return quick_substitution(history, substitution).map(Some);
+    }
+    let mut expanded = String::with_capacity(line.len());
+    let mut changed = false;
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut position = 0;
+    while let Some(ch) = line.get(position..).and_then(|rest| rest.chars().next()) {
+        position = position.saturating_add(ch.len_utf8());
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == '\'' {
+            quoted = !quoted;
+        } else if ch == '!' && !quoted {
+            let rest = line.get(position..).unwrap_or_default();
+            if let Some((reference, size)) = expand_reference(history, rest)? {
+                expanded.push_str(&reference);
+                position = position.saturating_add(size);
+                changed = true;
+                continue;
+            }
+        }
+        expanded.push(ch);
+    }
+    Ok(changed.then_some(expanded))
+}
+
+/// Expands the reference found after a `!`
+///
+/// Returns the expanded text and the size in bytes of the reference, or
+/// `None` if `rest` doesn't start with a reference.
+fn expand_reference(
+    history: &History,
+    rest: &str,
+) -> Result<Option<(String, usize)>, ExpansionError> {
+    let Some(first) = rest.chars().next() else {
+        return Ok(None);
+    };
+    let previous = history.len().checked_sub(1);
+    let (found, mut size) = match first {
+        '!' => (previous.and_then(|index| history.get(index)), 1),
+        '$' | '^' | '*' => (previous.and_then(|index| history.get(index)), 0),
+        '?' => {
+            let after = rest.get(1..).unwrap_or_default();
+            let closing = after.find('?');
+            let pattern = closing.and_then(|end| after.get(..end)).unwrap_or(after);
+            let size = pattern
+                .len()
+                .saturating_add(if closing.is_some() { 2 } else { 1 });
+            (history.search(pattern).next().map(|(_, line)| line), size)
+        }
+        '-' => {
+            let size = digits_len(rest.get(1..).unwrap_or_default());
+            if size == 0 {
+                return Ok(None);
+            }
+            let index = rest
+                .get(1..=size)
+                .and_then(|digits| digits.parse::<usize>().ok())
+                .and_then(|offset| history.len().checked_sub(offset));
+            (index.and_then(|idx| history.get(idx)), size.saturating_add(1))
+        }
+        _ if first.is_ascii_digit() => {
+            let size = digits_len(rest);
+            let index = rest
+                .get(..size)
+                .and_then(|digits| digits.parse::<usize>().ok())
+                .and_then(|number| number.checked_sub(1));
+            (index.and_then(|idx| history.get(idx)), size)
+        }
+        _ if first.is_whitespace() || matches!(first, '=' | '(') => return Ok(None),
+        _ => {
+            let size = rest
+                .find(|ch: char| ch.is_whitespace() || ch == ':')
+                .unwrap_or(rest.len());
+            if size == 0 {
+                return Ok(None);
+            }
+            let prefix = rest.get(..size).unwrap_or_default();
+            (
+                history
+                    .search_by(|line| line.starts_with(prefix))
+                    .next()
+                    .map(|(_, line)| line),
+                size,
+            )
+        }
+    };
+    let entry = found.ok_or_else(|| {
+        ExpansionError::EventNotFound(format!("!{}", rest.get(..size.max(1)).unwrap_or_default()))
+    })?;
+    let after = rest.get(size..).unwrap_or_default();
+    let selection = if size == 0 {
+        Designator::parse(after)
+    } else {
+        after
+            .strip_prefix(':')
+            .and_then(Designator::parse)
+            .map(|(designator, len)| (designator, len.saturating_add(1)))
+    };
+    let Some((designator, len)) = selection else {
+        return Ok(Some((entry.to_owned(), size)));
+    };
+    size = size.saturating_add(len);
+    let word = designator.select(entry).ok_or_else(|| {
+        ExpansionError::BadWordSpecifier(format!("!{}", rest.get(..size).unwrap_or_default()))
+    })?;
+    Ok(Some((word, size)))
+}
+
+/// Replaces the first occurrence of `old` by `new` in the previous line
+///
+/// `substitution` is the line without the leading `^`, i.e. `old^new^`, the
+/// last `^` being optional.
+fn quick_substitution(history: &History, substitution: &str) -> Result<String, ExpansionError> {
+    let (old, replacement) = substitution.split_once('^').unwrap_or((substitution, ""));
+    let new = replacement.strip_suffix('^').unwrap_or(replacement);
+    let previous = history
+        .len()
+        .checked_sub(1)
+        .and_then(|index| history.get(index))
+        .ok_or_else(|| ExpansionError::EventNotFound(format!("^{substitution}")))?;
+    if old.is_empty() || !previous.contains(old) {
+        return Err(ExpansionError::SubstitutionFailed);
+    }
+    Ok(previous.replacen(old, new, 1))
+}
+
+#[cfg(test)]
+#[expect(clippy::inline_modules, clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use super::{ExpansionError, History, expand};
+
+    /// Creates a history containing a few commands
+    fn history() -> History {
+        let mut history = History::default();
+        for line in ["ls -la /tmp", "git commit -m fix", "echo hello world"] {
+            history.push(line.to_owned()).unwrap();
+        }
+        history
+    }
+
+    /// Expands the line, which must contain a reference
+    fn expanded(line: &str) -> String {
+        expand(&history(), line).unwrap().unwrap()
+    }
+
+    /// Expands the line, which must fail
+    fn failure(line: &str) -> String {
+        expand(&history(), line).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn events() {
+        assert_eq!(expanded("!!"), "echo hello world");
+        assert_eq!(expanded("sudo !!"), "sudo echo hello world");
+        assert_eq!(expanded("!1"), "ls -la /tmp");
+        assert_eq!(expanded("!-2"), "git commit -m fix");
+        assert_eq!(expanded("!git"), "git commit -m fix");
+        assert_eq!(expanded("!?la? -R"), "ls -la /tmp -R");
+        assert_eq!(expanded("!?hello"), "echo hello world");
+    }
+
+    #[test]
+    fn missing_events() {
+        assert_eq!(failure("!4"), "!4: event not found");
+        assert_eq!(failure("!-4"), "!-4: event not found");
+        assert_eq!(failure("!nope"), "!nope: event not found");
+        assert_eq!(failure("!?nope?"), "!?nope?: event not found");
+        let empty = expand(&History::default(), "!!").unwrap_err();
+        assert!(matches!(empty, ExpansionError::EventNotFound(event) if event == "!!"));
+    }
+
+    #[test]
+    fn no_references() {
+        let history = history();
+        for line in ["echo hi", "a != b", "echo !", "echo '!!'", r"echo \!!"] {
+            assert_eq!(expand(&history, line).unwrap(), None, "{line}");
+        }
+        assert_eq!(expanded("echo '!!' !!"), "echo '!!' echo hello world");
+    }
+
+    #[test]
+    fn quick_substitutions() {
+        assert_eq!(expanded("^hello^bye"), "echo bye world");
+        assert_eq!(expanded("^hello^bye^"), "echo bye world");
+        assert_eq!(expanded("^hello ^"), "echo world");
+        assert_eq!(failure("^nope^yes"), "substitution failed");
+    }
+
+    #[test]
+    fn words() {
+        assert_eq!(expanded("!!:0"), "echo");
+        assert_eq!(expanded("!!:1"), "hello");
+        assert_eq!(expanded("!!:$"), "world");
+        assert_eq!(expanded("!-3:$"), "/tmp");
+        assert_eq!(expanded("!git:*"), "commit -m fix");
+        assert_eq!(expanded("cat !$ !^"), "cat world hello");
+        assert_eq!(expanded("echo !*"), "echo hello world");
+        assert_eq!(failure("!!:9"), "!!:9: bad word specifier");
+    }
+}
@@ -0,0 +1,346 @@
+//! Manage the submitted command history
+
+pub mod backend;
+pub mod expansion;
+pub mod namespaces;
+
+use core::slice::Iter;
+use std::io::Write;
+use std::path::Path;
+
+use backend::{FileBackend, HEADER, HistoryBackend, encode};
+
+use crate::IoResult;
+
+/// Contains all the previous submitted commands
+#[derive(Default)]
+pub struct History {
+    /// List of submitted commands
+    content: Vec<String>,
+    /// Cursor in the search of a command
+    cursor: usize,
+    /// Number of stored entries dropped from memory by the maximum length,
+    /// which stay before the others in the storage
+    dropped: usize,
+    /// Don't store a line if it is the same as the previous one
+    ignore_duplicates: bool,
+    /// Whether the entries were loaded from the storage in use
+    loaded: bool,
+    /// Maximum number of lines kept in memory
+    max_len: Option<usize>,
+    /// Storage used to keep the history between runs
+    store: Option<Box<dyn HistoryBackend>>,
+}
+
+impl History {
+    /// Sets the storage used to keep the history between runs
+    ///
+    /// The entries are not loaded from the new storage until [`History::load`]
+    /// is called.
+    pub fn backend<B>(&mut self, backend: B)
+    where
+        B: HistoryBackend + 'static,
+    {
+        self.store = Some(Box::new(backend));
+        self.dropped = 0;
+        self.loaded = false;
+    }
+
+    /// Removes all the entries from the history
+    ///
+    /// If the history is stored, the storage is emptied too.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the storage couldn't be emptied.
+    pub fn clear(&mut self) -> IoResult {
+        self.content.clear();
+        self.dropped = 0;
+        self.cursor_reset();
+        self.rewrite()
+    }
+
+    /// Move the cursor down
+    const fn cursor_down(&mut self) {
+        self.cursor = self.cursor.saturating_add(1);
+    }
+
+    /// Reset the cursor to the bottom of the pile
+    const fn cursor_reset(&mut self) {
+        self.cursor = self.content.len();
+    }
+
+    /// Move the cursor up
+    const fn cursor_up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    /// Goes down one in the history
+    pub fn down(&mut self) -> Option<&str> {
+        if self.cursor < self.content.len() {
+            self.cursor_down();
+            self.content.get(self.cursor).map(|line| &**line)
+        } else {
+            None
+        }
+    }
+
+    /// Goes down in the history to the next entry starting with `prefix`
+    pub fn down_starting_with(&mut self, prefix: &str) -> Option<&str> {
+        let start = self.cursor.saturating_add(1);
+        let index = self
+            .content
+            .get(start..)?
+            .iter()
+            .position(|line| line.starts_with(prefix))?;
+        self.cursor = start.saturating_add(index);
+        self.content.get(self.cursor).map(|line| &**line)
+    }
+
+    /// Writes every entry of the history, one per line, into the given writer
+    ///
+    /// The entries are written as in a [`FileBackend`], after its header, so
+    /// that the multi-line entries stay on a single line and the output can
+    /// be loaded back.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the writer fails.
+    pub fn export<W>(&self, mut writer: W) -> IoResult
+    where
+        W: Write,
+    {
+        writeln!(writer, "{HEADER}")?;
+        for line in &self.content {
+            writeln!(writer, "{}", encode(line))?;
+        }
+        writer.flush()
+    }
+
+    /// Goes to the oldest entry of the history
+    pub fn first(&mut self) -> Option<&str> {
+        self.cursor = 0;
+        self.content.first().map(|line| &**line)
+    }
+
+    /// Returns the entry at the given index, the oldest entry being at index 0
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.content.get(index).map(|line| &**line)
+    }
+
+    /// Don't store a line if it is identical to the previous one
+    pub const fn ignore_duplicates(&mut self, ignore: bool) {
+        self.ignore_duplicates = ignore;
+    }
+
+    /// Checks if the history contains no entries
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+
+    /// Iterates over the entries, from the oldest to the most recent
+    pub fn iter(&self) -> Iter<'_, String> {
+        self.content.iter()
+    }
+
+    /// Returns the number of entries in the history
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    /// Load history from the storage
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the storage couldn't be read.
+    pub fn load(&mut self) -> IoResult {
+        self.loaded = true;
+        if let Some(store) = &mut self.store {
+            self.content = store.load()?;
+            self.dropped = 0;
+            self.truncate();
+            self.cursor_reset();
+        }
+        Ok(())
+    }
+
+    /// Get the path of the file in which the history is stored, if any
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.store.as_ref()?.path()
+    }
+
+    /// Push a new line into the history
+    ///
+    /// This doesn't submit the line: it is only added to the history, as if it
+    /// had been entered by the user.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the line couldn't be written to the storage.
+    pub fn push(&mut self, line: String) -> IoResult {
+        if self.ignore_duplicates && self.content.last() == Some(&line) {
+            self.cursor_reset();
+            return Ok(());
+        }
+        if let Some(store) = &mut self.store {
+            store.append(&line)?;
+        }
+        self.content.push(line);
+        self.truncate();
+        self.cursor_reset();
+        Ok(())
+    }
+
+    /// Removes the entry at the given index and returns it
+    ///
+    /// If the history is stored, the storage is rewritten without the removed
+    /// entry, keeping the entries dropped by the maximum length.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the storage couldn't be rewritten.
+    pub fn remove(&mut self, index: usize) -> IoResult<Option<String>> {
+        if index >= self.content.len() {
+            return Ok(None);
+        }
+        let removed = self.content.remove(index);
+        self.cursor_reset();
+        self.rewrite()?;
+        Ok(Some(removed))
+    }
+
+    /// Goes back to the bottom of the history, after the most recent entry
+    pub const fn reset_cursor(&mut self) {
+        self.cursor_reset();
+    }
+
+    /// Rewrites the whole content of the history in the storage
+    fn rewrite(&mut self) -> IoResult {
+        if let Some(store) = &mut self.store {
+            let mut lines = store.load()?;
+            lines.truncate(self.dropped);
+            lines.extend_from_slice(&self.content);
+            store.rewrite(&lines)?;
+        }
+        Ok(())
+    }
+
+    /// Searches the entries containing the given pattern
+    ///
+    /// The results are returned with their index, from the most recent to the
+    /// oldest.
+    pub fn search<'pat>(
+        &self,
+        pattern: &'pat str,
+    ) -> impl Iterator<Item = (usize, &str)> + use<'_, 'pat> {
+        self.search_by(move |line| line.contains(pattern))
+    }
+
+    /// Searches the entries matching the given predicate
+    ///
+    /// This can be used to search with regular expressions. The results are
+    /// returned with their index, from the most recent to the oldest.
+    pub fn search_by<F>(&self, mut predicate: F) -> impl Iterator<Item = (usize, &str)> + use<'_, F>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        self.content
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, line)| (index, &**line))
+            .filter(move |(_, line)| predicate(line))
+    }
+
+    /// Sets the maximum number of entries kept in memory
+    ///
+    /// When the limit is reached, the oldest entries are dropped. `None` means
+    /// there is no limit, which is the default. The storage isn't affected:
+    /// the dropped entries are kept in it, even when it is rewritten.
+    pub fn set_max_len(&mut self, max_len: Option<usize>) {
+        self.max_len = max_len;
+        self.truncate();
+        self.cursor_reset();
+    }
+
+    /// Enables storage on the disk and provides the path to the storage file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the storage file couldn't be opened or created.
+    pub fn store(&mut self, path: String) -> IoResult {
+        self.backend(FileBackend::open(path)?);
+        Ok(())
+    }
+
+    /// Makes sure all the changes are persisted in the storage
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the changes couldn't be persisted.
+    pub fn sync(&mut self) -> IoResult {
+        self.store.as_mut().map_or(Ok(()), |store| store.sync())
+    }
+
+    /// Drops the oldest entries to respect the maximum length
+    fn truncate(&mut self) {
+        if let Some(overflow) = self
+            .max_len
+            .and_then(|max_len| self.content.len().checked_sub(max_len))
+        {
+            self.content.drain(..overflow);
+            self.dropped = self.dropped.saturating_add(overflow);
+        }
+    }
+
+    /// Goes up one in the history
+    pub fn up(&mut self) -> Option<&str> {
+        self.cursor_up();
+        self.content.get(self.cursor).map(|line| &**line)
+    }
+
+    /// Goes up in the history to the previous entry starting with `prefix`
+    pub fn up_starting_with(&mut self, prefix: &str) -> Option<&str> {
+        self.cursor = self
+            .content
+            .get(..self.cursor)?
+            .iter()
+            .rposition(|line| line.starts_with(prefix))?;
+        self.content.get(self.cursor).map(|line| &**line)
+    }
+}
+
+impl<'history> IntoIterator for &'history History {
+    type IntoIter = Iter<'history, String>;
+    type Item = &'history String;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::inline_modules, clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use super::History;
+    use super::backend::MemoryBackend;
+
+    #[test]
+    fn remove_keeps_dropped_entries() {
+        let store = MemoryBackend::new(["a", "b", "c"].map(String::from).to_vec());
+        let mut history = History::default();
+        history.backend(store.clone());
+        history.set_max_len(Some(2));
+        history.load().unwrap();
+        history.push("d".to_owned()).unwrap();
+        assert_eq!(history.iter().collect::<Vec<_>>(), ["c", "d"]);
+        assert_eq!(history.remove(0).unwrap().as_deref(), Some("c"));
+        assert_eq!(store.lines(), ["a", "b", "d"]);
+        history.clear().unwrap();
+        assert!(store.lines().is_empty());
+    }
+}
@@ -0,0 +1,149 @@
+//! Manage several independent histories, one per context of the application
+
+use core::mem::{replace, take};
+use std::collections::HashMap;
+
+use super::History;
+use crate::error::Error;
+
+/// Name of the history used when no other was selected
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+/// Contains all the named histories and the one currently in use
+///
+/// Each history is loaded from its storage the first time it is used.
+pub struct Histories {
+    /// History in use
+    current: History,
+    /// Outcomes of the loads not reported yet, with the name and the length
+    /// of the loaded histories
+    loads: Vec<Result<(String, usize), Error>>,
+    /// Name of the history in use
+    name: String,
+    /// All the other histories, indexed by their name
+    others: HashMap<String, History>,
+}
+
+impl Histories {
+    /// Get the history in use
+    pub const fn current(&self) -> &History {
+        &self.current
+    }
+
+    /// Get the history in use
+    pub const fn current_mut(&mut self) -> &mut History {
+        &mut self.current
+    }
+
+    /// Load the history in use from its storage, the first time only
+    ///
+    /// The outcome is kept until it is reported, see [`Histories::take_loads`].
+    pub fn load_current(&mut self) {
+        if !self.current.loaded {
+            let outcome = match self.current.load() {
+                Ok(()) => Ok((self.name.clone(), self.current.len())),
+                Err(err) => Err(Error::history(&self.current, err)),
+            };
+            self.loads.push(outcome);
+        }
+    }
+
+    /// Get the name of the history in use
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the history with the given name, creating it if it doesn't exist
+    pub fn namespace(&mut self, name: &str) -> &mut History {
+        if name == self.name {
+            &mut self.current
+        } else {
+            self.others.entry(name.to_owned()).or_default()
+        }
+    }
+
+    /// Change the history in use, creating it if it doesn't exist, and load
+    /// it if it wasn't
+    pub fn switch(&mut self, name: &str) {
+        if name != self.name {
+            let next = self.others.remove(name).unwrap_or_default();
+            let previous = replace(&mut self.current, next);
+            self.others
+                .insert(replace(&mut self.name, name.to_owned()), previous);
+        }
+        self.current.cursor_reset();
+        self.load_current();
+    }
+
+    /// Makes sure the changes of all the histories are persisted
+    pub fn sync(&mut self) -> Result<(), Error> {
+        self.current
+            .sync()
+            .map_err(|err| Error::history(&self.current, err))?;
+        self.others
+            .values_mut()
+            .try_for_each(|history| history.sync().map_err(|err| Error::history(history, err)))
+    }
+
+    /// Takes the outcomes of the loads since the last call, to report them
+    pub fn take_loads(&mut self) -> Vec<Result<(String, usize), Error>> {
+        take(&mut self.loads)
+    }
+}
+
+impl Default for Histories {
+    fn default() -> Self {
+        Self {
+            current: History::default(),
+            loads: vec![],
+            name: DEFAULT_NAMESPACE.to_owned(),
+            others: HashMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::inline_modules, clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use std::io;
+
+    use super::Histories;
+    use crate::history::backend::{HistoryBackend, MemoryBackend};
+
+    /// Storage which can't be read
+    struct Broken;
+
+    impl HistoryBackend for Broken {
+        fn append(&mut self, _line: &str) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn load(&mut self) -> io::Result<Vec<String>> {
+            Err(io::Error::other("broken"))
+        }
+
+        fn rewrite(&mut self, _lines: &[String]) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn load_on_first_switch() {
+        let mut histories = Histories::default();
+        histories.namespace("broken").backend(Broken);
+        histories.load_current();
+        histories.switch("broken");
+        histories
+            .namespace("sql")
+            .backend(MemoryBackend::new(vec!["select 1;".to_owned()]));
+        histories.switch("sql");
+        histories.switch("broken");
+        histories.switch("sql");
+        let mut loads = histories.take_loads().into_iter();
+        assert_eq!(loads.next().unwrap().unwrap(), ("default".to_owned(), 0));
+        loads.next().unwrap().unwrap_err();
+        assert_eq!(loads.next().unwrap().unwrap(), ("sql".to_owned(), 1));
+        assert!(loads.next().is_none());
+        assert_eq!(histories.current().get(0), Some("select 1;"));
+    }
+}
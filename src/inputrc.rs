@@ -0,0 +1,525 @@
+//! Parser for configuration files similar to readline's `.inputrc`
+//!
+//! Each line of the file is either:
+//!
+//! - a comment, starting with `#`;
+//! - a variable assignment, like `set editing-mode vi`;
+//! - a binding of a key name to a command or a macro, like `Control-u:
+//!   beginning-of-line` or `Meta-d: "date"`;
+//! - a binding of a key sequence to a command or a macro, like `"\C-x\C-e":
+//!   end-of-line` or `"\ep": "print "`;
+//! - a conditional construct: `$if mode=emacs`, `$if term=xterm`, `$else` and
+//!   `$endif`.
+
+use core::time::Duration;
+use std::env;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::command::Command;
+use crate::error::Error;
+use crate::key::{Binding, Key};
+use crate::settings::EditingMode;
+
+/// Instruction found on a line of the configuration file
+pub enum Entry {
+    /// Bind a sequence of keys
+    Bind(Vec<Key>, Binding),
+    /// Set a variable
+    Set(Variable),
+}
+
+/// Variable that can be set in the configuration file
+#[derive(Clone, Copy)]
+pub enum Variable {
+    /// `completion-ignore-case`
+    CompletionIgnoreCase(bool),
+    /// `editing-mode`
+    EditingMode(EditingMode),
+    /// `history-size`, a negative size meaning no limit
+    HistorySize(Option<usize>),
+    /// `keyseq-timeout`, in milliseconds
+    KeyseqTimeout(Duration),
+}
+
+/// State of the parser, used to evaluate the conditional constructs
+struct Parser {
+    /// Value of each enclosing `$if`
+    conditions: Vec<bool>,
+    /// Editing mode, used to evaluate `$if mode=...`
+    mode: EditingMode,
+}
+
+impl Parser {
+    /// Checks if the current line is in a branch that must be ignored
+    fn is_skipping(&self) -> bool {
+        self.conditions.contains(&false)
+    }
+
+    /// Parses a line starting with `$`
+    fn parse_directive(&mut self, directive: &str) -> Result<(), String> {
+        let (name, argument) = split_word(directive);
+        match name {
+            "if" => {
+                let condition = if let Some(mode) = argument.strip_prefix("mode=") {
+                    parse_editing_mode(mode)? == self.mode
+                } else if let Some(term) = argument.strip_prefix("term=") {
+                    env::var("TERM")
+                        .is_ok_and(|value| value == term || value.split('-').next() == Some(term))
+                } else {
+                    false
+                };
+                self.conditions.push(condition);
+            }
+            "else" => {
+                let last = self
+                    .conditions
+                    .last_mut()
+                    .ok_or_else(|| "$else without $if".to_owned())?;
+                *last = !*last;
+            }
+            "endif" => {
+                self.conditions
+                    .pop()
+                    .ok_or_else(|| "$endif without $if".to_owned())?;
+            }
+            _ => return Err(format!("unsupported directive `${name}`")),
+        }
+        Ok(())
+    }
+
+    /// Parses a line that isn't empty, a comment nor a directive
+    fn parse_line(&mut self, line: &str) -> Result<Entry, String> {
+        if let Some(assignment) = line.strip_prefix("set ") {
+            let variable = parse_variable(assignment.trim())?;
+            if let Variable::EditingMode(mode) = variable {
+                self.mode = mode;
+            }
+            return Ok(Entry::Set(variable));
+        }
+        let (sequence, value) = if let Some(quoted) = line.strip_prefix('"') {
+            let end =
+                closing_quote(quoted, '"').ok_or_else(|| "missing closing quote".to_owned())?;
+            let value = quoted
+                .get(end.saturating_add(1)..)
+                .and_then(|rest| rest.trim_start().strip_prefix(':'))
+                .ok_or_else(|| "missing `:` after the key sequence".to_owned())?;
+            (parse_sequence(quoted.get(..end).unwrap_or_default())?, value)
+        } else {
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| "missing `:` after the key name".to_owned())?;
+            (vec![parse_key_name(name.trim())?], value)
+        };
+        Ok(Entry::Bind(sequence, parse_binding(value.trim())?))
+    }
+}
+
+/// Returns the index of the closing quote, skipping the escaped characters
+fn closing_quote(text: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    text.char_indices().find_map(|(index, ch)| {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == quote {
+            return Some(index);
+        }
+        None
+    })
+}
+
+/// Parses the content of a configuration file
+///
+/// `mode` is the editing mode before the file is read. Every instruction is
+/// returned with the number of its line.
+pub fn parse(content: &str, mode: EditingMode) -> Vec<Result<(usize, Entry), Error>> {
+    let mut parser = Parser { conditions: vec![], mode };
+    let mut entries = vec![];
+    for (index, raw_line) in content.lines().enumerate() {
+        let number = index.saturating_add(1);
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let result = if let Some(directive) = line.strip_prefix('$') {
+            parser.parse_directive(directive).map(|()| None)
+        } else if parser.is_skipping() {
+            Ok(None)
+        } else {
+            parser.parse_line(line).map(Some)
+        };
+        match result {
+            Ok(Some(entry)) => entries.push(Ok((number, entry))),
+            Ok(None) => (),
+            Err(message) => entries.push(Err(Error::Config { line: number, message })),
+        }
+    }
+    if !parser.conditions.is_empty() {
+        let message = "missing $endif".to_owned();
+        entries.push(Err(Error::Config { line: content.lines().count(), message }));
+    }
+    entries
+}
+
+/// Parses what a key is bound to: a command name or a quoted macro
+///
+/// The names that aren't built-in commands are returned as
+/// [`Binding::Named`], as they may be defined by the user.
+fn parse_binding(value: &str) -> Result<Binding, String> {
+    for quote in ['"', '\''] {
+        if let Some(quoted) = value.strip_prefix(quote) {
+            let end =
+                closing_quote(quoted, quote).ok_or_else(|| "missing closing quote".to_owned())?;
+            return Ok(Binding::Macro(unescape(quoted.get(..end).unwrap_or_default())));
+        }
+    }
+    let (name, _) = split_word(value);
+    if name.is_empty() {
+        return Err("missing command name".to_owned());
+    }
+    Ok(Command::from_name(name).map_or_else(|| Binding::Named(name.to_owned()), Binding::Command))
+}
+
+/// Parses a boolean value of a variable
+fn parse_bool(value: &str) -> Result<bool, String> {
+    if value.eq_ignore_ascii_case("on") || value == "1" {
+        Ok(true)
+    } else if value.eq_ignore_ascii_case("off") || value == "0" {
+        Ok(false)
+    } else {
+        Err(format!("invalid boolean `{value}`, expected `on` or `off`"))
+    }
+}
+
+/// Parses the value of the `editing-mode` variable
+fn parse_editing_mode(value: &str) -> Result<EditingMode, String> {
+    match value {
+        "emacs" => Ok(EditingMode::Emacs),
+        "vi" => Ok(EditingMode::Vi),
+        _ => Err(format!("invalid editing mode `{value}`, expected `emacs` or `vi`")),
+    }
+}
+
+/// Parses a key name, like `Control-u`, `Meta-Rubout` or `F5`
+fn parse_key_name(name: &str) -> Result<Key, String> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = name;
+    loop {
+        let lower = rest.to_ascii_lowercase();
+        if lower.starts_with("control-") || lower.starts_with("c-") && rest.len() > 2 {
+            modifiers |= KeyModifiers::CONTROL;
+        } else if lower.starts_with("meta-") || lower.starts_with("m-") && rest.len() > 2 {
+            modifiers |= KeyModifiers::ALT;
+        } else {
+            break;
+        }
+        rest = rest.split_once('-').map_or("", |(_, after)| after);
+    }
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(ch), None) => KeyCode::Char(if modifiers.contains(KeyModifiers::CONTROL) {
+            ch.to_ascii_lowercase()
+        } else {
+            ch
+        }),
+        _ => match rest.to_ascii_lowercase().as_str() {
+            "del" | "rubout" => KeyCode::Backspace,
+            "esc" | "escape" => KeyCode::Esc,
+            "lfd" | "newline" =>
+                return Ok(Key::new(KeyCode::Char('j'), modifiers | KeyModifiers::CONTROL)),
+            "ret" | "return" | "enter" => KeyCode::Enter,
+            "spc" | "space" => KeyCode::Char(' '),
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "insert" => KeyCode::Insert,
+            "delete" => KeyCode::Delete,
+            lower => lower
+                .strip_prefix('f')
+                .and_then(|number| number.parse().ok())
+                .map(KeyCode::F)
+                .ok_or_else(|| format!("unknown key name `{name}`"))?,
+        },
+    };
+    Ok(Key::new(code, modifiers))
+}
+
+/// Parses a quoted key sequence, like `\C-x\C-e` or `\e[A`
+fn parse_sequence(sequence: &str) -> Result<Vec<Key>, String> {
+    let mut keys = vec![];
+    let mut rest = sequence;
+    while !rest.is_empty() {
+        let (key, size) = parse_sequence_key(rest)?;
+        keys.push(key);
+        rest = rest.get(size..).unwrap_or_default();
+    }
+    if keys.is_empty() {
+        return Err("empty key sequence".to_owned());
+    }
+    Ok(keys)
+}
+
+/// Parses the first key of a quoted key sequence
+///
+/// Returns the key and its size in bytes in the sequence.
+fn parse_sequence_key(sequence: &str) -> Result<(Key, usize), String> {
+    /// Escape sequences sent by the terminals for the special keys
+    const ESCAPE_SEQUENCES: [(&str, KeyCode); 16] = [
+        ("[A", KeyCode::Up),
+        ("[B", KeyCode::Down),
+        ("[C", KeyCode::Right),
+        ("[D", KeyCode::Left),
+        ("[H", KeyCode::Home),
+        ("[F", KeyCode::End),
+        ("OA", KeyCode::Up),
+        ("OB", KeyCode::Down),
+        ("OC", KeyCode::Right),
+        ("OD", KeyCode::Left),
+        ("OH", KeyCode::Home),
+        ("OF", KeyCode::End),
+        ("[2~", KeyCode::Insert),
+        ("[3~", KeyCode::Delete),
+        ("[5~", KeyCode::PageUp),
+        ("[6~", KeyCode::PageDown),
+    ];
+    let mut chars = sequence.chars();
+    let first = chars
+        .next()
+        .ok_or_else(|| "empty key sequence".to_owned())?;
+    if first != '\\' {
+        return Ok((Key::plain(KeyCode::Char(first)), first.len_utf8()));
+    }
+    let rest = sequence.get(1..).unwrap_or_default();
+    if let Some(after) = rest.strip_prefix("C-") {
+        let (key, size) = parse_sequence_key(after)?;
+        return Ok((control(key)?, size.saturating_add(3)));
+    }
+    if let Some(after) = rest.strip_prefix("M-") {
+        let (key, size) = parse_sequence_key(after)?;
+        return Ok((
+            Key::new(key.code(), key.modifiers() | KeyModifiers::ALT),
+            size.saturating_add(3),
+        ));
+    }
+    let escaped = chars
+        .next()
+        .ok_or_else(|| "trailing backslash in key sequence".to_owned())?;
+    let key = match escaped {
+        'e' => {
+            let after = rest.get(1..).unwrap_or_default();
+            if let Some((escape, code)) = ESCAPE_SEQUENCES
+                .iter()
+                .find(|(escape, _)| after.starts_with(escape))
+            {
+                return Ok((Key::plain(*code), escape.len().saturating_add(2)));
+            }
+            match after.chars().next() {
+                Some(ch) if ch != '\\' => {
+                    return Ok((Key::alt(KeyCode::Char(ch)), ch.len_utf8().saturating_add(2)));
+                }
+                _ => Key::plain(KeyCode::Esc),
+            }
+        }
+        'a' => Key::ctrl('g'),
+        'b' => Key::ctrl('h'),
+        'd' => Key::plain(KeyCode::Backspace),
+        'f' => Key::ctrl('l'),
+        'n' => Key::ctrl('j'),
+        'r' => Key::plain(KeyCode::Enter),
+        't' => Key::plain(KeyCode::Tab),
+        '\\' | '"' | '\'' => Key::plain(KeyCode::Char(escaped)),
+        _ => return Err(format!("unknown escape sequence `\\{escaped}`")),
+    };
+    Ok((key, escaped.len_utf8().saturating_add(1)))
+}
+
+/// Parses a variable assignment, without the leading `set`
+fn parse_variable(assignment: &str) -> Result<Variable, String> {
+    let (name, value) = split_word(assignment);
+    match name.to_ascii_lowercase().as_str() {
+        "completion-ignore-case" => parse_bool(value).map(Variable::CompletionIgnoreCase),
+        "editing-mode" => parse_editing_mode(value).map(Variable::EditingMode),
+        "history-size" => value
+            .parse::<isize>()
+            .map(|size| Variable::HistorySize(usize::try_from(size).ok()))
+            .map_err(|err| format!("invalid history size `{value}`: {err}")),
+        "keyseq-timeout" => value
+            .parse()
+            .map(|millis| Variable::KeyseqTimeout(Duration::from_millis(millis)))
+            .map_err(|err| format!("invalid timeout `{value}`: {err}")),
+        _ => Err(format!("unknown variable `{name}`")),
+    }
+}
+
+/// Applies the `Control` modifier to a key of a sequence
+fn control(key: Key) -> Result<Key, String> {
+    let KeyCode::Char(ch) = key.code() else {
+        return Err(format!("`Control` can't be applied to `{}`", key.code()));
+    };
+    Ok(match ch {
+        '?' => Key::new(KeyCode::Backspace, key.modifiers()),
+        '[' => Key::new(KeyCode::Esc, key.modifiers()),
+        'i' | 'I' => Key::new(KeyCode::Tab, key.modifiers()),
+        'm' | 'M' => Key::new(KeyCode::Enter, key.modifiers()),
+        _ => Key::new(
+            KeyCode::Char(ch.to_ascii_lowercase()),
+            key.modifiers() | KeyModifiers::CONTROL,
+        ),
+    })
+}
+
+/// Splits a line at the first whitespace
+fn split_word(line: &str) -> (&str, &str) {
+    line.split_once(char::is_whitespace)
+        .map_or((line, ""), |(word, rest)| (word, rest.trim()))
+}
+
+/// Replaces the escape sequences of a macro by the corresponding characters
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('t') => unescaped.push('\t'),
+                Some(other) => unescaped.push(other),
+                None => unescaped.push('\\'),
+            }
+        } else {
+            unescaped.push(ch);
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+#[expect(clippy::inline_modules, clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    use super::{Entry, Variable, parse, parse_key_name, parse_sequence};
+    use crate::command::Command;
+    use crate::error::Error;
+    use crate::key::{Binding, Key};
+    use crate::settings::EditingMode;
+
+    /// Parses the content in emacs mode, and returns the errors with their
+    /// line numbers
+    fn errors(content: &str) -> Vec<(usize, String)> {
+        parse(content, EditingMode::Emacs)
+            .into_iter()
+            .filter_map(|result| match result {
+                Err(Error::Config { line, message }) => Some((line, message)),
+                Ok(_) | Err(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn conditionals() {
+        let content = "$if mode=vi\n\"\\C-a\": end-of-line\n$else\nControl-a: beginning-of-line\n\
+                       $endif\nset editing-mode vi\n$if mode=vi\nMeta-x: \"vi\\n\"\n$endif";
+        let entries: Vec<_> = parse(content, EditingMode::Emacs)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert!(matches!(
+            entries.as_slice(),
+            [
+                (4, Entry::Bind(first, Binding::Command(Command::BeginningOfLine))),
+                (6, Entry::Set(Variable::EditingMode(EditingMode::Vi))),
+                (8, Entry::Bind(second, Binding::Macro(text))),
+            ] if *first == [Key::ctrl('a')] && *second == [Key::alt(KeyCode::Char('x'))]
+                && text == "vi\n"
+        ));
+    }
+
+    #[test]
+    fn error_lines() {
+        let content = "set bell-style none\nControl-u beginning-of-line\n\"\\C-x: end-of-line\n\
+                       $else\n$endif\n$foo\nHyper-x: end-of-line\n$if term=nope";
+        let expected = [
+            (1, "unknown variable `bell-style`"),
+            (2, "missing `:` after the key name"),
+            (3, "missing closing quote"),
+            (4, "$else without $if"),
+            (5, "$endif without $if"),
+            (6, "unsupported directive `$foo`"),
+            (7, "unknown key name `Hyper-x`"),
+            (8, "missing $endif"),
+        ]
+        .map(|(line, message)| (line, message.to_owned()));
+        assert_eq!(errors(content), expected);
+    }
+
+    #[test]
+    fn key_names() {
+        let ctrl_alt = KeyModifiers::CONTROL | KeyModifiers::ALT;
+        let names = [
+            ("Control-u", Key::ctrl('u')),
+            ("C-U", Key::ctrl('u')),
+            ("Meta-Rubout", Key::alt(KeyCode::Backspace)),
+            ("Meta-Control-x", Key::new(KeyCode::Char('x'), ctrl_alt)),
+            ("M-C-x", Key::new(KeyCode::Char('x'), ctrl_alt)),
+            ("LFD", Key::ctrl('j')),
+            ("Space", Key::plain(KeyCode::Char(' '))),
+            ("Return", Key::plain(KeyCode::Enter)),
+            ("F5", Key::plain(KeyCode::F(5))),
+            ("x", Key::plain(KeyCode::Char('x'))),
+        ];
+        for (name, key) in names {
+            assert_eq!(parse_key_name(name).unwrap(), key, "{name}");
+        }
+        parse_key_name("Control-Hyper").unwrap_err();
+    }
+
+    #[test]
+    fn sequences() {
+        let sequences = [
+            (r"\C-x\C-e", vec![Key::ctrl('x'), Key::ctrl('e')]),
+            (r"\e[A", vec![Key::plain(KeyCode::Up)]),
+            (r"\eOB", vec![Key::plain(KeyCode::Down)]),
+            (r"\e[3~", vec![Key::plain(KeyCode::Delete)]),
+            (r"\ep", vec![Key::alt(KeyCode::Char('p'))]),
+            (r"\M-d", vec![Key::alt(KeyCode::Char('d'))]),
+            (
+                r"\M-\C-h",
+                vec![Key::new(
+                    KeyCode::Char('h'),
+                    KeyModifiers::CONTROL | KeyModifiers::ALT,
+                )],
+            ),
+            (r"\C-?", vec![Key::plain(KeyCode::Backspace)]),
+            (r"\e\\", vec![Key::plain(KeyCode::Esc), Key::plain(KeyCode::Char('\\'))]),
+            (
+                "ab",
+                vec![
+                    Key::plain(KeyCode::Char('a')),
+                    Key::plain(KeyCode::Char('b')),
+                ],
+            ),
+        ];
+        for (sequence, keys) in sequences {
+            assert_eq!(parse_sequence(sequence).unwrap(), keys, "{sequence}");
+        }
+        let errors = [
+            (r"\q", "unknown escape sequence `\\q`"),
+            ("a\\", "trailing backslash in key sequence"),
+            (r"\C-\e[A", "`Control` can't be applied to `Up`"),
+            ("", "empty key sequence"),
+        ];
+        for (sequence, message) in errors {
+            assert_eq!(parse_sequence(sequence).unwrap_err(), message, "{sequence}");
+        }
+    }
+}
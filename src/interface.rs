@@ -1,64 +1,408 @@
 //! Defines the [`AppInterface`] data structure, used to interface with the
 //! client.
 
-use core::cmp;
-use core::mem::take;
+use core::mem::{replace, take};
+use core::str;
+use std::io;
+
+use crate::history::namespaces::Histories;
+use crate::line::Line;
+use crate::printer::raw_text;
+use crate::{History, IoResult, Terminal};
 
 /// Interface provided to the client to configure the CLI.
 ///
 /// It provides functionalities to update and manage the execution. For
 /// instance, it provides ways to exit the program.
-#[derive(Default)]
 pub struct AppInterface<'line> {
-    /// Current line to execute
-    line: &'line str,
+    /// Names of the commands to execute after the handler
+    commands: Vec<String>,
+    /// Whether the line is being edited, i.e. it is drawn and the terminal
+    /// is in raw mode
+    editing: bool,
+    /// Histories of the submitted lines
+    histories: &'line mut Histories,
+    /// Line being edited, or submitted line
+    line: &'line mut Line,
+    /// Whether the output of the handler doesn't end with a newline
+    partial: bool,
+    /// Whether the handler printed something
+    printed: bool,
     /// Informs the runner what to do at the next step.
     status: ReturnStatus,
+    /// Terminal on which the line is drawn
+    terminal: &'line mut dyn Terminal,
 }
 
 impl<'line> AppInterface<'line> {
+    /// Abandon the line being edited and start a new one.
+    ///
+    /// `^C` is printed after the line, like when Ctrl-C is pressed in a
+    /// shell.
+    pub const fn abort(&mut self) {
+        self.status.add(ReturnStatus::ABORT);
+    }
+
     /// Clear the screen.
-    pub fn clear_screen(&mut self) {
-        self.status.add(ReturnStatus::ClearScreen);
+    pub const fn clear_screen(&mut self) {
+        self.status.add(ReturnStatus::CLEAR_SCREEN);
+    }
+
+    /// Get the position of the cursor on the line, in bytes.
+    #[must_use]
+    pub const fn cursor(&self) -> usize {
+        self.line.cursor()
     }
 
-    /// Kill the process.
-    pub fn exit(&mut self) {
-        self.status.add(ReturnStatus::Exit);
+    /// Ends the output of the handler with a newline, so that the runner
+    /// draws the line below it
+    pub(super) fn end_output(&mut self) -> IoResult {
+        if !self.partial || !self.terminal.is_interactive() {
+            return Ok(());
+        }
+        self.partial = false;
+        self.terminal
+            .write(if self.editing { "\r\n" } else { "\n" })
     }
 
-    /// Get the currently executed line.
+    /// Print some text to the standard error, followed by a newline.
+    ///
+    /// See [`AppInterface::print`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal couldn't be written.
+    pub fn eprintln(&mut self, text: &str) -> IoResult {
+        self.output(&format!("{text}\n"), true)
+    }
+
+    /// Stop the runner.
+    ///
+    /// [`App::run`](crate::App::run) returns the exit code `0`.
+    pub const fn exit(&mut self) {
+        self.exit_with_code(0);
+    }
+
+    /// Stop the runner with the given exit code.
+    ///
+    /// The code is returned by [`App::run`](crate::App::run).
+    pub const fn exit_with_code(&mut self, code: i32) {
+        self.status.add(ReturnStatus::exit(code));
+    }
+
+    /// Get the history of the submitted lines.
+    ///
+    /// It can be used to read, search or edit the previous lines. This is the
+    /// history in use, see [`AppInterface::switch_history`].
+    pub const fn history_mut(&mut self) -> &mut History {
+        self.histories.current_mut()
+    }
+
+    /// Get the name of the history in use.
     #[must_use]
-    pub const fn line(&self) -> &str {
+    pub fn history_name(&self) -> &str {
+        self.histories.name()
+    }
+
+    /// Insert some text at the cursor.
+    ///
+    /// The line is redrawn after the handler returns.
+    pub fn insert(&mut self, text: &str) {
+        self.line.insert_str(text);
+    }
+
+    /// Get the current line.
+    ///
+    /// In a key handler, this is the line being edited. When a line is
+    /// submitted, this is the submitted line.
+    #[must_use]
+    pub fn line(&self) -> &str {
         self.line
     }
 
+    /// Move the cursor to the given position, in bytes.
+    ///
+    /// Nothing is done if the position isn't the start of a character of the
+    /// line. Use the end of the line, [`str::len`], to move after the last
+    /// character.
+    pub fn move_cursor(&mut self, position: usize) {
+        self.line.set_cursor(position);
+    }
+
     /// Creates a default [`AppInterface`]
-    pub(super) const fn new(line: &'line str) -> Self {
-        Self { line, status: ReturnStatus::None }
+    ///
+    /// `editing` tells whether the handler is called while the line is being
+    /// edited, rather than after it was submitted.
+    pub(super) fn new(
+        line: &'line mut Line,
+        histories: &'line mut Histories,
+        terminal: &'line mut dyn Terminal,
+        editing: bool,
+    ) -> Self {
+        Self {
+            commands: vec![],
+            editing,
+            histories,
+            line,
+            partial: false,
+            printed: false,
+            status: ReturnStatus::NONE,
+            terminal,
+        }
+    }
+
+    /// Writes the output of the handler, erasing the line being edited the
+    /// first time
+    fn output(&mut self, text: &str, error: bool) -> IoResult {
+        if text.is_empty() {
+            return Ok(());
+        }
+        let written = if self.editing {
+            if !self.printed {
+                self.line.erase(&mut *self.terminal)?;
+            }
+            raw_text(text)
+        } else {
+            text.to_owned()
+        };
+        self.printed = true;
+        self.partial = !text.ends_with('\n');
+        if error {
+            self.terminal.write_error(&written)
+        } else {
+            self.terminal.write(&written)
+        }
+    }
+
+    /// Print some text to the standard output.
+    ///
+    /// Unlike [`print!`], the newlines are translated for the raw mode, and
+    /// the line being edited is erased before the first output of a key
+    /// handler. The line is printed again below the output after the handler
+    /// returns.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal couldn't be written.
+    pub fn print(&mut self, text: &str) -> IoResult {
+        self.output(text, false)
+    }
+
+    /// Print some text to the standard output, followed by a newline.
+    ///
+    /// See [`AppInterface::print`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal couldn't be written.
+    pub fn println(&mut self, text: &str) -> IoResult {
+        self.output(&format!("{text}\n"), false)
+    }
+
+    /// Print the line again after the handler returns.
+    ///
+    /// This is useful if the handler printed something.
+    pub const fn redraw(&mut self) {
+        self.status.add(ReturnStatus::REDRAW);
+    }
+
+    /// Replaces the whole line, and moves the cursor to its end.
+    ///
+    /// When a line is submitted, this changes the line stored in the history.
+    pub fn replace_line(&mut self, line: String) {
+        self.line.replace(line);
+    }
+
+    /// Executes a command by its name, e.g. `kill-word`.
+    ///
+    /// The command can be built-in or defined by the user. It is executed
+    /// after the handler returns. A command defined by the user isn't
+    /// executed again while it is running, e.g. if it runs itself: this is
+    /// reported as an
+    /// [`Error::RecursiveCommand`](crate::Error::RecursiveCommand).
+    ///
+    /// The commands edit the line, so they are ignored when the standard
+    /// input isn't a terminal and the lines are read from it without editing.
+    pub fn run_command(&mut self, name: &str) {
+        self.commands.push(name.to_owned());
+    }
+
+    /// Submits the line being edited after the handler returns.
+    ///
+    /// This has no effect when the line is already being submitted.
+    pub const fn submit(&mut self) {
+        self.status.add(ReturnStatus::SUBMIT);
+    }
+
+    /// Suspend the process after the handler returns, like Ctrl-Z in a
+    /// shell.
+    ///
+    /// When the process is resumed, the line is printed again. The process
+    /// is stopped by the terminal, see
+    /// [`Terminal::suspend`](crate::Terminal::suspend): not on platforms
+    /// other than Unix, nor with a [`VirtualTerminal`](crate::VirtualTerminal).
+    pub const fn suspend(&mut self) {
+        self.status.add(ReturnStatus::SUSPEND);
+    }
+
+    /// Change the history in use.
+    ///
+    /// Every context of the application can have its own history. The history
+    /// is created if it doesn't exist. The line being submitted is stored in
+    /// the new history.
+    pub fn switch_history(&mut self, name: &str) {
+        self.histories.switch(name);
+    }
+
+    /// Returns the names of the commands to execute
+    pub(super) fn take_commands(&mut self) -> Vec<String> {
+        take(&mut self.commands)
     }
 
     /// Returns the [`ReturnStatus`]
     pub(super) fn take_status(&mut self) -> ReturnStatus {
         take(&mut self.status)
     }
+
+    /// Get a writer printing to the standard output, e.g. for [`write!`].
+    ///
+    /// The output is handled like with [`AppInterface::print`].
+    pub fn writer(&mut self) -> impl io::Write {
+        Writer { interface: self, pending: vec![] }
+    }
 }
 
 /// Actions to be executed by the runner
-#[repr(u8)]
-#[derive(Default, PartialOrd, PartialEq, Eq, Ord, Clone, Copy)]
-pub enum ReturnStatus {
-    ClearScreen = 1,
-    Exit = 2,
-    #[default]
-    None = 0,
+///
+/// The actions requested by the handlers are combined: all of them are
+/// executed, the line being submitted first and the runner exiting last.
+#[expect(clippy::struct_excessive_bools, reason = "independent actions")]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ReturnStatus {
+    /// Abandon the line and start a new one
+    abort: bool,
+    /// Clear the screen
+    clear_screen: bool,
+    /// Exit the runner with the given code
+    exit: Option<i32>,
+    /// Print the line again
+    redraw: bool,
+    /// Submit the line
+    submit: bool,
+    /// Suspend the process
+    suspend: bool,
 }
 
 impl ReturnStatus {
+    /// Abandon the line and start a new one
+    pub(crate) const ABORT: Self = Self { abort: true, ..Self::NONE };
+    /// Clear the screen
+    pub(crate) const CLEAR_SCREEN: Self = Self { clear_screen: true, ..Self::NONE };
+    /// Nothing to do
+    pub(crate) const NONE: Self = Self {
+        abort: false,
+        clear_screen: false,
+        exit: None,
+        redraw: false,
+        submit: false,
+        suspend: false,
+    };
+    /// Print the line again
+    pub(crate) const REDRAW: Self = Self { redraw: true, ..Self::NONE };
+    /// Submit the line
+    pub(crate) const SUBMIT: Self = Self { submit: true, ..Self::NONE };
+    /// Suspend the process
+    pub(crate) const SUSPEND: Self = Self { suspend: true, ..Self::NONE };
+
+    /// Checks if the line must be abandoned
+    pub(crate) const fn abort(&self) -> bool {
+        self.abort
+    }
+
     /// Specify an additional status to be executed
     ///
-    /// Only the most severe one is executed because it contains the others.
-    fn add(&mut self, other: Self) {
-        *self = cmp::max(*self, other);
+    /// If both request to exit, the last exit code is kept.
+    pub(crate) const fn add(&mut self, other: Self) {
+        self.abort |= other.abort;
+        self.clear_screen |= other.clear_screen;
+        self.redraw |= other.redraw;
+        self.submit |= other.submit;
+        self.suspend |= other.suspend;
+        if other.exit.is_some() {
+            self.exit = other.exit;
+        }
+    }
+
+    /// Checks if the screen must be cleared
+    pub(crate) const fn clear_screen(&self) -> bool {
+        self.clear_screen
+    }
+
+    /// Exit the runner with the given code
+    pub(crate) const fn exit(code: i32) -> Self {
+        Self { exit: Some(code), ..Self::NONE }
+    }
+
+    /// Get the exit code, if the runner must exit
+    pub(crate) const fn exit_code(&self) -> Option<i32> {
+        self.exit
+    }
+
+    /// Checks if the line must be printed again
+    pub(crate) const fn redraw(&self) -> bool {
+        self.redraw
+    }
+
+    /// Checks if the process must be suspended
+    pub(crate) const fn suspend(&self) -> bool {
+        self.suspend
+    }
+
+    /// Checks if the line must be submitted, and cancel the submission
+    pub(crate) const fn take_submit(&mut self) -> bool {
+        let submit = self.submit;
+        self.submit = false;
+        submit
+    }
+}
+
+/// Writer of the output of a handler, see [`AppInterface::writer`]
+struct Writer<'interface, 'line> {
+    /// Interface through which the output is printed
+    interface: &'interface mut AppInterface<'line>,
+    /// Bytes of an incomplete character, waiting for the next ones
+    pending: Vec<u8>,
+}
+
+impl Writer<'_, '_> {
+    /// Prints the pending bytes, except an incomplete character at their end
+    /// unless `all` is set
+    fn print_pending(&mut self, all: bool) -> IoResult {
+        let complete = match str::from_utf8(&self.pending) {
+            Err(err) if !all && err.error_len().is_none() => err.valid_up_to(),
+            Ok(_) | Err(_) => self.pending.len(),
+        };
+        let rest = self.pending.split_off(complete);
+        let bytes = replace(&mut self.pending, rest);
+        self.interface.print(&String::from_utf8_lossy(&bytes))
+    }
+}
+
+impl Drop for Writer<'_, '_> {
+    fn drop(&mut self) {
+        self.print_pending(true).unwrap_or_default();
+    }
+}
+
+impl io::Write for Writer<'_, '_> {
+    fn flush(&mut self) -> IoResult {
+        self.print_pending(true)?;
+        self.interface.terminal.flush()
+    }
+
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        self.print_pending(false)?;
+        Ok(buf.len())
     }
 }
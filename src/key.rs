@@ -1,41 +1,407 @@
 //! Defines the key handlers
 
+use core::error::Error;
+use core::fmt;
+use core::mem::take;
+use core::time::Duration;
 use std::collections::HashMap;
 
-use crate::AppInterface;
-use crate::interface::ReturnStatus;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::command::Command;
 use crate::runner::Action;
 
-/// All the keys that can be overridden
-#[non_exhaustive]
-#[derive(PartialEq, Eq, Hash)]
-pub enum Key {
-    /// Enter key
-    Escape,
+/// Default time to wait for the next key of a sequence
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Key combination that can be bound to a command or an action
+///
+/// A key is made of a [`KeyCode`] and the [`KeyModifiers`] held while it was
+/// pressed, for instance `Ctrl-X`, `Alt-Enter`, `F5` or `Shift-Tab`.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub struct Key {
+    /// Key that was pressed
+    code: KeyCode,
+    /// Modifiers held while the key was pressed
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    /// Escape key
+    pub const ESCAPE: Self = Self::plain(KeyCode::Esc);
+
+    /// Creates a [`Key`] pressed while holding the `Alt` modifier
+    #[must_use]
+    pub const fn alt(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::ALT)
+    }
+
+    /// Get the pressed key
+    #[must_use]
+    pub const fn code(&self) -> KeyCode {
+        self.code
+    }
+
+    /// Creates a [`Key`] for a character pressed while holding the `Ctrl`
+    /// modifier
+    #[must_use]
+    pub const fn ctrl(ch: char) -> Self {
+        Self::new(KeyCode::Char(ch.to_ascii_lowercase()), KeyModifiers::CONTROL)
+    }
+
+    /// Get the held modifiers
+    #[must_use]
+    pub const fn modifiers(&self) -> KeyModifiers {
+        self.modifiers
+    }
+
+    /// Creates a [`Key`] from a key code and modifiers
+    ///
+    /// The `Shift` modifier is ignored for characters, as it is already
+    /// applied to the character (`Shift-a` is `A`). `Shift-Tab` is
+    /// [`KeyCode::BackTab`].
+    #[must_use]
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let without_shift = modifiers.difference(KeyModifiers::SHIFT);
+        if matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+            Self { code, modifiers: without_shift }
+        } else if matches!(code, KeyCode::Tab) && modifiers.contains(KeyModifiers::SHIFT) {
+            Self { code: KeyCode::BackTab, modifiers: without_shift }
+        } else {
+            Self { code, modifiers }
+        }
+    }
+
+    /// Creates a [`Key`] pressed without any modifiers
+    #[must_use]
+    pub const fn plain(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
 }
 
+impl fmt::Display for Key {
+    /// Displays the key in the emacs notation, e.g. `C-x`, `M-Enter` or `F5`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            "C-".fmt(f)?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            "M-".fmt(f)?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            "S-".fmt(f)?;
+        }
+        self.code.fmt(f)
+    }
+}
+
+impl From<KeyCode> for Key {
+    fn from(code: KeyCode) -> Self {
+        Self::plain(code)
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+/// Action executed by the user when a key is pressed
 pub type KeyPress = Box<dyn Action>;
 
+/// What is executed when a key is pressed
+#[non_exhaustive]
+pub enum Binding {
+    /// User-defined action
+    Action(KeyPress),
+    /// Built-in editing command
+    Command(Command),
+    /// Text inserted into the line
+    Macro(String),
+    /// Command invoked by name, either built-in or defined by the user
+    Named(String),
+}
+
+/// Error returned when a sequence can't be bound because it conflicts with an
+/// existing binding
+///
+/// A sequence conflicts with another one if one of them is a prefix of the
+/// other, like `C-x` and `C-x C-e`: when `C-x` is pressed, it wouldn't be
+/// possible to know which one to execute.
+///
+/// Readline accepts such bindings and runs the shorter one once the time to
+/// wait for the next key elapses. They are rejected here instead: the shorter
+/// binding would be delayed on every press, and a conflict found when binding
+/// is reported to the caller, or as an error of the `inputrc` line, rather
+/// than making a key slow without notice. The keys of a pending sequence
+/// which times out are handled as unbound keys.
+#[derive(Debug)]
+pub struct KeyConflict {
+    /// Sequence that is already bound
+    existing: Vec<Key>,
+    /// Sequence that couldn't be bound
+    sequence: Vec<Key>,
+}
+
+impl KeyConflict {
+    /// Get the sequence that is already bound
+    #[must_use]
+    pub fn existing(&self) -> &[Key] {
+        &self.existing
+    }
+
+    /// Get the sequence that couldn't be bound
+    #[must_use]
+    pub fn sequence(&self) -> &[Key] {
+        &self.sequence
+    }
+}
+
+impl fmt::Display for KeyConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` conflicts with the existing binding `{}`",
+            DisplaySequence(&self.sequence),
+            DisplaySequence(&self.existing)
+        )
+    }
+}
+
+impl Error for KeyConflict {}
+
+/// Displays a sequence of keys, separated by spaces
+pub struct DisplaySequence<'keys>(pub &'keys [Key]);
+
+impl fmt::Display for DisplaySequence<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, key) in self.0.iter().enumerate() {
+            if index != 0 {
+                ' '.fmt(f)?;
+            }
+            key.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Result of a key press
+pub enum Resolution<'keys> {
+    /// The pressed keys form a bound sequence
+    Bound(&'keys mut Binding),
+    /// The pressed keys are the start of a bound sequence
+    Pending,
+    /// The pressed keys don't match any bound sequence
+    Unbound(Vec<Key>),
+}
+
 /// Contains all the defined handlers for a given set of keys
-pub struct Keys(HashMap<Key, KeyPress>);
+pub struct Keys {
+    /// Commands and actions executed when a sequence of keys is pressed
+    bindings: HashMap<Vec<Key>, Binding>,
+    /// Keys pressed that are the start of a bound sequence
+    pending: Vec<Key>,
+    /// Time to wait for the next key of a sequence
+    timeout: Duration,
+}
 
 impl Keys {
-    /// Define an action for a key
-    pub fn define_key(&mut self, key: Key, action: impl Action + 'static) -> Option<()> {
-        self.0.insert(key, Box::new(action)).map(|_old| ())
+    /// Define what is executed when a key is pressed
+    ///
+    /// Returns the previous binding of the key, if any.
+    pub fn define_key(
+        &mut self,
+        key: Key,
+        binding: Binding,
+    ) -> Result<Option<Binding>, KeyConflict> {
+        self.define_sequence(vec![key], binding)
+    }
+
+    /// Define what is executed when a sequence of keys is pressed
+    ///
+    /// Returns the previous binding of the sequence, if any, or an error if
+    /// the sequence is a prefix of a bound sequence or the other way round.
+    pub fn define_sequence(
+        &mut self,
+        sequence: Vec<Key>,
+        binding: Binding,
+    ) -> Result<Option<Binding>, KeyConflict> {
+        if let Some(existing) = self.bindings.keys().find(|existing| {
+            let (shorter, longer) = if existing.len() < sequence.len() {
+                (existing.as_slice(), sequence.as_slice())
+            } else {
+                (sequence.as_slice(), existing.as_slice())
+            };
+            shorter.len() != longer.len() && longer.starts_with(shorter)
+        }) {
+            return Err(KeyConflict { existing: existing.clone(), sequence });
+        }
+        Ok(self.bindings.insert(sequence, binding))
     }
 
-    /// Fire an action after [`Key`] was pressed
-    pub fn fire_key(&mut self, key: &Key, line: &str) -> ReturnStatus {
-        self.0.get_mut(key).map_or(ReturnStatus::None, |handler| {
-            let mut app = AppInterface::new(line);
-            handler(&mut app);
-            app.take_status()
-        })
+    /// Abandon the pending sequence and return its keys
+    pub fn flush(&mut self) -> Vec<Key> {
+        take(&mut self.pending)
     }
 
-    /// Creates a default [`Keys`]
+    /// Creates a [`Keys`] with the default bindings
     pub fn new() -> Self {
-        Self(HashMap::new())
+        let defaults = [
+            (KeyCode::Enter.into(), Command::AcceptLine),
+            (KeyCode::Backspace.into(), Command::BackwardDeleteChar),
+            (KeyCode::Delete.into(), Command::DeleteChar),
+            (KeyCode::Left.into(), Command::BackwardChar),
+            (KeyCode::Right.into(), Command::ForwardChar),
+            (KeyCode::Home.into(), Command::BeginningOfLine),
+            (KeyCode::End.into(), Command::EndOfLine),
+            (KeyCode::Up.into(), Command::UpLineOrHistory),
+            (KeyCode::Down.into(), Command::DownLineOrHistory),
+            (Key::new(KeyCode::Left, KeyModifiers::CONTROL), Command::BackwardWord),
+            (Key::new(KeyCode::Right, KeyModifiers::CONTROL), Command::ForwardWord),
+            (Key::ctrl('a'), Command::BeginningOfLine),
+            (Key::ctrl('b'), Command::BackwardChar),
+            (Key::ctrl('c'), Command::Interrupt),
+            (Key::ctrl('d'), Command::EndOfFile),
+            (Key::ctrl('e'), Command::EndOfLine),
+            (Key::ctrl('f'), Command::ForwardChar),
+            (Key::ctrl('k'), Command::KillLine),
+            (Key::ctrl('l'), Command::ClearScreen),
+            (Key::ctrl('n'), Command::NextHistory),
+            (Key::ctrl('p'), Command::PreviousHistory),
+            (Key::ctrl('t'), Command::TransposeChars),
+            (Key::ctrl('u'), Command::BackwardKillLine),
+            (Key::ctrl('w'), Command::BackwardKillWord),
+            (Key::ctrl('y'), Command::Yank),
+            (Key::ctrl('z'), Command::Suspend),
+            (Key::alt(KeyCode::Backspace), Command::BackwardKillWord),
+            (Key::alt(KeyCode::Enter), Command::InsertNewline),
+            (Key::alt(KeyCode::Char('<')), Command::BeginningOfHistory),
+            (Key::alt(KeyCode::Char('>')), Command::EndOfHistory),
+            (Key::alt(KeyCode::Char('b')), Command::BackwardWord),
+            (Key::alt(KeyCode::Char('d')), Command::KillWord),
+            (Key::alt(KeyCode::Char('f')), Command::ForwardWord),
+            (Key::alt(KeyCode::Char('n')), Command::HistorySearchForward),
+            (Key::alt(KeyCode::Char('p')), Command::HistorySearchBackward),
+        ];
+        Self {
+            bindings: defaults
+                .into_iter()
+                .map(|(key, command)| (vec![key], Binding::Command(command)))
+                .collect(),
+            pending: vec![],
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Get the keys of the sequence being typed
+    pub fn pending(&self) -> &[Key] {
+        &self.pending
+    }
+
+    /// Time to wait for the next key of the pending sequence
+    ///
+    /// Returns `None` if no sequence is pending.
+    pub fn pending_timeout(&self) -> Option<Duration> {
+        (!self.pending.is_empty()).then_some(self.timeout)
+    }
+
+    /// Registers a pressed key and returns what must be executed
+    pub fn press(&mut self, key: Key) -> Resolution<'_> {
+        self.pending.push(key);
+        if self.bindings.keys().any(|sequence| {
+            sequence.len() > self.pending.len() && sequence.starts_with(&self.pending)
+        }) {
+            return Resolution::Pending;
+        }
+        let sequence = take(&mut self.pending);
+        self.bindings
+            .get_mut(&sequence)
+            .map_or(Resolution::Unbound(sequence), Resolution::Bound)
+    }
+
+    /// Removes the binding of a sequence of keys
+    ///
+    /// Returns the previous binding of the sequence, if any.
+    pub fn remove(&mut self, sequence: &[Key]) -> Option<Binding> {
+        self.bindings.remove(sequence)
+    }
+
+    /// Sets the time to wait for the next key of a sequence
+    pub const fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::inline_modules, clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use super::*;
+
+    /// Builds a sequence of `Ctrl` keys
+    fn ctrl(chars: &str) -> Vec<Key> {
+        chars.chars().map(Key::ctrl).collect()
+    }
+
+    #[test]
+    fn conflicts() {
+        let mut keys = Keys::new();
+        keys.define_sequence(ctrl("xe"), Binding::Command(Command::EndOfLine))
+            .unwrap();
+        let conflict = keys
+            .define_key(Key::ctrl('x'), Binding::Command(Command::KillLine))
+            .err()
+            .unwrap();
+        assert_eq!(conflict.existing(), ctrl("xe"));
+        assert_eq!(conflict.sequence(), ctrl("x"));
+        assert_eq!(conflict.to_string(), "`C-x` conflicts with the existing binding `C-x C-e`");
+        let longer = keys
+            .define_sequence(ctrl("xex"), Binding::Command(Command::KillLine))
+            .err()
+            .unwrap();
+        assert_eq!(longer.existing(), ctrl("xe"));
+        let previous = keys
+            .define_sequence(ctrl("xe"), Binding::Command(Command::KillLine))
+            .unwrap();
+        assert!(matches!(previous, Some(Binding::Command(Command::EndOfLine))));
+        keys.define_sequence(ctrl("xk"), Binding::Command(Command::KillLine))
+            .unwrap();
+    }
+
+    #[test]
+    fn pending() {
+        let mut keys = Keys::new();
+        keys.define_sequence(ctrl("xe"), Binding::Command(Command::EndOfLine))
+            .unwrap();
+        assert_eq!(keys.pending_timeout(), None);
+        assert!(matches!(keys.press(Key::ctrl('x')), Resolution::Pending));
+        assert_eq!(keys.pending(), ctrl("x"));
+        assert_eq!(keys.pending_timeout(), Some(DEFAULT_TIMEOUT));
+        assert!(matches!(
+            keys.press(Key::ctrl('e')),
+            Resolution::Bound(Binding::Command(Command::EndOfLine))
+        ));
+        assert!(keys.pending().is_empty());
+        assert!(matches!(keys.press(Key::ctrl('x')), Resolution::Pending));
+        assert_eq!(keys.flush(), ctrl("x"));
+        assert_eq!(keys.pending_timeout(), None);
+        keys.press(Key::ctrl('x'));
+        let unbound = [Key::ctrl('x'), KeyCode::Char('a').into()];
+        assert!(matches!(
+            keys.press(KeyCode::Char('a').into()),
+            Resolution::Unbound(pressed) if pressed == unbound
+        ));
+    }
+
+    #[test]
+    fn remove() {
+        let mut keys = Keys::new();
+        keys.define_sequence(ctrl("xe"), Binding::Command(Command::EndOfLine))
+            .unwrap();
+        assert!(matches!(keys.remove(&ctrl("xe")), Some(Binding::Command(Command::EndOfLine))));
+        assert!(keys.remove(&ctrl("xe")).is_none());
+        assert!(matches!(keys.press(Key::ctrl('x')), Resolution::Unbound(_)));
+        keys.define_key(Key::ctrl('x'), Binding::Command(Command::KillLine))
+            .unwrap();
     }
 }
@@ -50,31 +50,45 @@
 #![allow(clippy::blanket_clippy_restriction_lints, reason = "enable all lints")]
 #![allow(clippy::print_stdout, reason = "crate's goal")]
 
+extern crate alloc;
+
+mod command;
+mod diagnostics;
+mod error;
+mod highlight;
 mod history;
+mod inputrc;
 mod interface;
 mod key;
 mod line;
+mod printer;
 mod runner;
+mod settings;
+mod terminal;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod validate;
 
-use std::io::{self, Write as _};
+use std::io;
 
+pub use command::Command;
+pub use crossterm::event::{KeyCode, KeyModifiers};
+pub use crossterm::style::{Color, ContentStyle, Stylize};
+pub use crossterm::terminal::ClearType;
+pub use diagnostics::{Level, LogEvent};
+pub use error::Error;
+pub use highlight::{Highlighter, MatchingBrackets, Span, Tokens};
+pub use history::History;
+pub use history::backend::{FileBackend, HistoryBackend, MemoryBackend};
+pub use history::expansion::ExpansionError;
 pub use interface::AppInterface;
-pub use key::Key;
+pub use key::{Binding, Key, KeyConflict};
+pub use printer::ExternalPrinter;
 pub use runner::App;
-
-/// Size of the prefix displayed at every start of line.
-const PREFIX_SIZE: u16 = 4;
+pub use settings::{EditingMode, Settings};
+pub use terminal::emulator::VirtualTerminal;
+pub use terminal::{CrosstermTerminal, Rendering, Terminal};
+pub use validate::{Balanced, TrailingBackslash, Validation, Validator};
 
 /// Result to handle io errors
 type IoResult<T = ()> = Result<T, io::Error>;
-
-/// Print without new line but flush anyway.
-fn print_code_line(line: &str) {
-    print!("\r>>> {line}");
-}
-
-/// Print without new line but flush anyway.
-fn print_code_line_flush(line: &str) -> IoResult {
-    print_code_line(line);
-    io::stdout().flush()
-}
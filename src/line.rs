@@ -1,84 +1,602 @@
 //! Manage the submitted command history
 
-use core::mem::take;
-use core::ops::Deref;
-use std::io::stdout;
+use core::mem::{replace, take};
+use core::ops::{Deref, Range};
 
-use crossterm::ExecutableCommand as _;
-use crossterm::cursor::MoveToColumn;
+use crossterm::style::{ContentStyle, Stylize as _};
+use crossterm::terminal::ClearType;
 
-use crate::{IoResult, PREFIX_SIZE, print_code_line, print_code_line_flush};
+use crate::IoResult;
+use crate::highlight::{Highlighter, Span, paint};
+use crate::printer::raw_text;
+use crate::terminal::{Rendering, Terminal};
+
+/// Prompt displayed at the start of the following rows of a multi-line
+/// line if none was provided
+const DEFAULT_CONTINUATION: &str = "... ";
+
+/// Prompt displayed at the start of the line if none was provided
+const DEFAULT_PROMPT: &str = ">>> ";
 
 /// Contains the current line status
-#[derive(Default)]
 pub struct Line {
+    /// Whether colors can be used
+    color: bool,
     /// Line content
     content: String,
-    /// Current position of the cursor on the line
+    /// Text displayed before the following rows of a multi-line line
+    continuation: String,
+    /// Current position of the cursor on the line, in bytes
     cursor: usize,
+    /// Row of the cursor on the terminal, from the first row of the line,
+    /// counting the rows wrapped by the terminal except in dumb rendering
+    cursor_row: usize,
+    /// Number of characters displayed by the last print, to blank them in
+    /// dumb rendering
+    drawn: usize,
+    /// Position of the cursor when the line was last drawn highlighted
+    highlighted_cursor: Option<usize>,
+    /// Highlighter of the line, used if the colors are enabled
+    highlighter: Option<Box<dyn Highlighter>>,
+    /// Last text removed by a kill command
+    killed: String,
+    /// Text displayed before the line
+    prompt: String,
+    /// How the line is drawn on the terminal
+    rendering: Rendering,
 }
 
 impl Line {
     /// Remove a character from the line
-    #[expect(clippy::arithmetic_side_effects, reason = "manual check")]
-    pub fn backspace(&mut self) -> IoResult {
-        if self.cursor > 0 {
-            self.cursor -= 1;
-            self.content.remove(self.cursor);
+    pub fn backspace(&mut self, terminal: &mut dyn Terminal) -> IoResult {
+        if let Some(previous) = self.previous_boundary() {
+            self.content.remove(previous);
+            self.cursor = previous;
         }
-        print_code_line_flush(&format!("{} ", self.content))
+        self.redraw(terminal)
+    }
+
+    /// Kill the text from the start of the line to the cursor
+    pub fn backward_kill_line(&mut self, terminal: &mut dyn Terminal) -> IoResult {
+        self.kill(terminal, 0..self.cursor)
+    }
+
+    /// Kill the word before the cursor
+    pub fn backward_kill_word(&mut self, terminal: &mut dyn Terminal) -> IoResult {
+        self.kill(terminal, self.previous_word()..self.cursor)
+    }
+
+    /// Move the cursor to the start of the current or previous word
+    pub fn backward_word(&mut self) {
+        self.cursor = self.previous_word();
+    }
+
+    /// Get the text before the cursor
+    pub fn before_cursor(&self) -> &str {
+        self.content.get(..self.cursor).unwrap_or_default()
+    }
+
+    /// Checks if colors can be used
+    pub const fn color(&self) -> bool {
+        self.color
+    }
+
+    /// Get the column of the cursor in its row, in characters
+    fn column(&self) -> usize {
+        self.before_cursor()
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .count()
+    }
+
+    /// Get the position of the cursor on the line, in bytes
+    pub const fn cursor(&self) -> usize {
+        self.cursor
     }
 
     /// Move the cursor left
-    #[expect(clippy::arithmetic_side_effects, reason = "manual check")]
-    pub const fn decrease_counter(&mut self) {
-        if self.cursor > 0 {
-            self.cursor -= 1;
+    pub fn decrease_counter(&mut self) {
+        if let Some(previous) = self.previous_boundary() {
+            self.cursor = previous;
         }
     }
 
+    /// Remove the character under the cursor
+    pub fn delete(&mut self, terminal: &mut dyn Terminal) -> IoResult {
+        if self.next_boundary().is_some() {
+            self.content.remove(self.cursor);
+        }
+        self.redraw(terminal)
+    }
+
+    /// Erases the line from the terminal, leaving the cursor at the start of
+    /// its first row
+    ///
+    /// In dumb rendering, only the row of the cursor is erased.
+    pub fn erase(&mut self, terminal: &mut dyn Terminal) -> IoResult {
+        match self.rendering {
+            Rendering::Dumb => {
+                let blank = " ".repeat(take(&mut self.drawn));
+                terminal.write(&format!("\r{blank}\r"))
+            }
+            Rendering::Full => {
+                terminal.move_up(rows(take(&mut self.cursor_row)))?;
+                terminal.write("\r")?;
+                terminal.clear(ClearType::FromCursorDown)
+            }
+        }
+    }
+
+    /// Move the cursor to the end of the current or next word
+    pub fn forward_word(&mut self) {
+        self.cursor = self.next_word();
+    }
+
     /// Move the cursor right
-    #[expect(clippy::arithmetic_side_effects, reason = "manual check")]
-    pub const fn increase_counter(&mut self) {
-        if self.cursor < self.content.len() {
-            self.cursor += 1;
+    pub fn increase_counter(&mut self) {
+        if let Some(next) = self.next_boundary() {
+            self.cursor = next;
         }
     }
 
     /// Insert a character into the line
-    pub fn insert(&mut self, ch: char) -> IoResult {
+    pub fn insert(&mut self, terminal: &mut dyn Terminal, ch: char) -> IoResult {
         self.content.insert(self.cursor, ch);
-        self.cursor = self.cursor.saturating_add(1);
-        print_code_line_flush(&self.content)
+        self.cursor = self.cursor.saturating_add(ch.len_utf8());
+        self.redraw(terminal)
     }
 
-    /// Sets the whole line
-    pub fn set(&mut self, line: String) -> IoResult {
-        print_code_line(&" ".repeat(self.content.len()));
-        self.cursor = line.len().checked_sub(1).unwrap_or_default();
+    /// Insert some text at the cursor, without redrawing the line
+    pub fn insert_str(&mut self, text: &str) {
+        self.content.insert_str(self.cursor, text);
+        self.cursor = self.cursor.saturating_add(text.len());
+    }
+
+    /// Remove the text in the given range and keep it to be yanked later
+    fn kill(&mut self, terminal: &mut dyn Terminal, range: Range<usize>) -> IoResult {
+        if !range.is_empty() {
+            self.cursor = range.start;
+            self.killed = self.content.drain(range).collect();
+        }
+        self.redraw(terminal)
+    }
+
+    /// Kill the text from the cursor to the end of the line
+    pub fn kill_line(&mut self, terminal: &mut dyn Terminal) -> IoResult {
+        self.kill(terminal, self.cursor..self.content.len())
+    }
+
+    /// Kill the text from the cursor to the end of the current or next word
+    pub fn kill_word(&mut self, terminal: &mut dyn Terminal) -> IoResult {
+        self.kill(terminal, self.cursor..self.next_word())
+    }
+
+    /// Move the cursor to the next row, at the same column if it is long
+    /// enough
+    ///
+    /// Returns `false` if the cursor is on the last row.
+    pub fn move_down(&mut self) -> bool {
+        let end = self.row_end();
+        if end == self.content.len() {
+            return false;
+        }
+        let column = self.column();
+        let start = end.saturating_add(1);
+        self.cursor = start;
+        self.cursor = self.position_in_row(start, self.row_end(), column);
+        true
+    }
+
+    /// Moves the cursor of the terminal to the end of the line, e.g. to print
+    /// something after it
+    ///
+    /// In dumb rendering, the cursor only moves to the last row.
+    pub fn move_past_end(&mut self, terminal: &mut dyn Terminal) -> IoResult {
+        if self.rendering == Rendering::Dumb {
+            self.cursor_row = self.content.matches('\n').count();
+            return Ok(());
+        }
+        let (row, column) = end_position(&self.widths(""), columns(terminal));
+        self.move_to_row(terminal, row)?;
+        terminal.move_to_column(u16::try_from(column).unwrap_or(u16::MAX))
+    }
+
+    /// Move the cursor to the end of its row
+    pub fn move_to_end(&mut self) {
+        self.cursor = self.row_end();
+    }
+
+    /// Moves the cursor of the terminal to the given row from the first row
+    /// of the line, in full rendering
+    fn move_to_row(&mut self, terminal: &mut dyn Terminal, row: usize) -> IoResult {
+        if row < self.cursor_row {
+            terminal.move_up(rows(self.cursor_row.saturating_sub(row)))?;
+        } else {
+            terminal.move_down(rows(row.saturating_sub(self.cursor_row)))?;
+        }
+        self.cursor_row = row;
+        Ok(())
+    }
+
+    /// Move the cursor to the start of its row
+    pub fn move_to_start(&mut self) {
+        self.cursor = self.row_start();
+    }
+
+    /// Move the cursor to the previous row, at the same column if it is long
+    /// enough
+    ///
+    /// Returns `false` if the cursor is on the first row.
+    pub fn move_up(&mut self) -> bool {
+        let Some(end) = self.row_start().checked_sub(1) else {
+            return false;
+        };
+        let column = self.column();
+        self.cursor = end;
+        self.cursor = self.position_in_row(self.row_start(), end, column);
+        true
+    }
+
+    /// Position of the character after the cursor
+    fn next_boundary(&self) -> Option<usize> {
+        self.content
+            .get(self.cursor..)
+            .and_then(|after| after.chars().next())
+            .map(|ch| self.cursor.saturating_add(ch.len_utf8()))
+    }
+
+    /// Position of the end of the current or next word
+    fn next_word(&self) -> usize {
+        let after = self.content.get(self.cursor..).unwrap_or_default();
+        let start = after.find(char::is_alphanumeric).unwrap_or(after.len());
+        let word = after.get(start..).unwrap_or_default();
+        let end = word
+            .find(|ch: char| !ch.is_alphanumeric())
+            .unwrap_or(word.len());
+        self.cursor.saturating_add(start).saturating_add(end)
+    }
+
+    /// Get the rows of the content, with the styles of the highlighter if it
+    /// is used
+    ///
+    /// Each row is painted on its own, so that no style spills over the
+    /// prompt of the next row.
+    fn painted_rows(&mut self) -> Vec<String> {
+        let spans = match &self.highlighter {
+            Some(highlighter) if self.color && self.rendering == Rendering::Full => {
+                self.highlighted_cursor = Some(self.cursor);
+                highlighter.highlight(&self.content, self.cursor)
+            }
+            Some(_) | None => {
+                self.highlighted_cursor = None;
+                return self.content.split('\n').map(str::to_owned).collect();
+            }
+        };
+        let mut start = 0usize;
+        self.content
+            .split('\n')
+            .map(|row| {
+                let end = start.saturating_add(row.len());
+                let shifted: Vec<_> = spans
+                    .iter()
+                    .filter_map(|span| {
+                        let range = span.range.start.max(start)..span.range.end.min(end);
+                        (!range.is_empty()).then(|| {
+                            let shift = |index: usize| index.saturating_sub(start);
+                            Span::new(shift(range.start)..shift(range.end), span.style)
+                        })
+                    })
+                    .collect();
+                start = end.saturating_add(1);
+                paint(row, &shifted)
+            })
+            .collect()
+    }
+
+    /// Position of the given column in the row between the given positions,
+    /// or of the end of the row if it is too short
+    fn position_in_row(&self, start: usize, end: usize, column: usize) -> usize {
+        self.content
+            .get(start..end)
+            .and_then(|row| row.char_indices().nth(column))
+            .map_or(end, |(index, _)| start.saturating_add(index))
+    }
+
+    /// Position of the character before the cursor
+    fn previous_boundary(&self) -> Option<usize> {
+        self.before_cursor()
+            .char_indices()
+            .next_back()
+            .map(|(index, _)| index)
+    }
+
+    /// Position of the start of the current or previous word
+    fn previous_word(&self) -> usize {
+        let before = self.before_cursor();
+        let end = before
+            .rfind(char::is_alphanumeric)
+            .map_or(0, |index| index.saturating_add(1));
+        before
+            .get(..end)
+            .unwrap_or_default()
+            .char_indices()
+            .rev()
+            .take_while(|(_, ch)| ch.is_alphanumeric())
+            .last()
+            .map_or(end, |(index, _)| index)
+    }
+
+    /// Print the line followed by a hint, e.g. the pending key sequence
+    ///
+    /// The following rows of a multi-line line start with the continuation
+    /// prompt, and what was displayed below the line is cleared. In dumb
+    /// rendering, only the row of the cursor is printed, on a new row if the
+    /// cursor moved down, and the end of the previous hint is overwritten with
+    /// spaces.
+    pub fn print_hint(&mut self, terminal: &mut dyn Terminal, hint: &str) -> IoResult {
+        if self.rendering == Rendering::Dumb {
+            return self.print_row(terminal, hint);
+        }
+        terminal.move_up(rows(self.cursor_row))?;
+        let columns = columns(terminal);
+        let widths = self.widths(hint);
+        for ((row, text), width) in self.painted_rows().iter().enumerate().zip(&widths) {
+            if row > 0 {
+                terminal.write("\r\n")?;
+            }
+            terminal.write(&format!("\r{}{text}", self.row_prompt(row)))?;
+            if row.saturating_add(1) < widths.len() && !fills_rows(*width, columns) {
+                terminal.clear(ClearType::UntilNewLine)?;
+            }
+        }
+        terminal.write(hint)?;
+        if widths
+            .last()
+            .is_some_and(|&width| fills_rows(width, columns))
+        {
+            // Leaves the last column, where the cursor stays after filling it
+            terminal.write("\r\n")?;
+        }
+        self.cursor_row = end_position(&widths, columns).0;
+        terminal.clear(ClearType::FromCursorDown)
+    }
+
+    /// Print the row of the cursor followed by a hint, in dumb rendering
+    fn print_row(&mut self, terminal: &mut dyn Terminal, hint: &str) -> IoResult {
+        let row = self.before_cursor().matches('\n').count();
+        if row > self.cursor_row {
+            terminal.write(&"\r\n".repeat(row.saturating_sub(self.cursor_row)))?;
+            self.drawn = 0;
+        }
+        self.cursor_row = row;
+        let content = self.content.split('\n').nth(row).unwrap_or_default();
+        let text = format!("\r{}{content}{hint}", self.row_prompt(row));
+        terminal.write(&text)?;
+        let width = text.chars().count().saturating_sub(1);
+        let blank = self.drawn.saturating_sub(width);
+        self.drawn = width;
+        terminal.write(&" ".repeat(blank))
+    }
+
+    /// Print the line again, clearing what was displayed after it
+    pub fn redraw(&mut self, terminal: &mut dyn Terminal) -> IoResult {
+        self.print_hint(terminal, "")
+    }
+
+    /// Print the line again if it was highlighted with the cursor elsewhere
+    pub fn refresh(&mut self, terminal: &mut dyn Terminal) -> IoResult {
+        match self.highlighted_cursor {
+            Some(cursor) if cursor != self.cursor => self.redraw(terminal),
+            Some(_) | None => Ok(()),
+        }
+    }
+
+    /// Get how the line is drawn on the terminal
+    pub const fn rendering(&self) -> Rendering {
+        self.rendering
+    }
+
+    /// Replaces the whole line, without redrawing it
+    pub fn replace(&mut self, line: String) {
         self.content = line;
-        print_code_line_flush(&self.content)
+        self.cursor = self.content.len();
+    }
+
+    /// Position of the end of the row of the cursor
+    fn row_end(&self) -> usize {
+        let after = self.content.get(self.cursor..).unwrap_or_default();
+        self.cursor
+            .saturating_add(after.find('\n').unwrap_or(after.len()))
+    }
+
+    /// Get the text displayed before the given row of the line
+    fn row_prompt(&self, row: usize) -> &str {
+        if row == 0 {
+            &self.prompt
+        } else {
+            &self.continuation
+        }
+    }
+
+    /// Position of the start of the row of the cursor
+    fn row_start(&self) -> usize {
+        self.before_cursor()
+            .rfind('\n')
+            .map_or(0, |index| index.saturating_add(1))
+    }
+
+    /// Sets the whole line
+    pub fn set(&mut self, terminal: &mut dyn Terminal, line: String) -> IoResult {
+        self.replace(line);
+        self.redraw(terminal)
+    }
+
+    /// Enables or disables the colors
+    pub const fn set_color(&mut self, enabled: bool) {
+        self.color = enabled;
+    }
+
+    /// Sets the text displayed before the following rows of a multi-line line
+    pub fn set_continuation(&mut self, prompt: String) {
+        self.continuation = prompt;
+    }
+
+    /// Move the cursor to the given position, in bytes
+    ///
+    /// Nothing is done if the position isn't the start of a character.
+    pub fn set_cursor(&mut self, position: usize) {
+        if self.content.is_char_boundary(position) {
+            self.cursor = position;
+        }
+    }
+
+    /// Sets the highlighter of the line
+    pub fn set_highlighter(&mut self, highlighter: Box<dyn Highlighter>) {
+        self.highlighter = Some(highlighter);
+    }
+
+    /// Sets the text displayed before the line
+    ///
+    /// Returns the previous prompt.
+    pub const fn set_prompt(&mut self, prompt: String) -> String {
+        replace(&mut self.prompt, prompt)
+    }
+
+    /// Sets how the line is drawn on the terminal
+    pub const fn set_rendering(&mut self, rendering: Rendering) {
+        self.rendering = rendering;
+    }
+
+    /// Shows an error message below the line, until the line is printed
+    /// again
+    ///
+    /// In dumb rendering, the row of the cursor is printed again below the
+    /// message.
+    pub fn show_error(&mut self, terminal: &mut dyn Terminal, message: &str) -> IoResult {
+        self.redraw(terminal)?;
+        let styled = if self.color && self.rendering == Rendering::Full {
+            ContentStyle::new().red().apply(message).to_string()
+        } else {
+            message.to_owned()
+        };
+        terminal.write(&raw_text(&format!("\n{styled}")))?;
+        match self.rendering {
+            Rendering::Dumb => {
+                terminal.write("\r\n")?;
+                self.drawn = 0;
+                self.redraw(terminal)
+            }
+            Rendering::Full => {
+                let widths: Vec<_> = message.split('\n').map(|row| row.chars().count()).collect();
+                terminal.move_up(rows(height(&widths, columns(terminal))))
+            }
+        }
     }
 
     /// Resets the line and returns the content
     pub fn take(&mut self) -> String {
-        take(self).content
+        self.cursor = 0;
+        self.cursor_row = 0;
+        take(&mut self.content)
     }
 
-    /// Decrease the cursor on the terminal and inside the app
+    /// Swap the character before the cursor with the one under the cursor
     ///
-    /// # Panics
+    /// At the end of the line, the two last characters are swapped.
+    pub fn transpose_chars(&mut self, terminal: &mut dyn Terminal) -> IoResult {
+        if self.next_boundary().is_none() {
+            self.decrease_counter();
+        }
+        if let Some(previous) = self.previous_boundary()
+            && let Some(next) = self.next_boundary()
+        {
+            let ch = self.content.remove(previous);
+            self.content.insert(next.saturating_sub(ch.len_utf8()), ch);
+            self.cursor = next;
+        }
+        self.redraw(terminal)
+    }
+
+    /// Moves the cursor of the terminal to the cursor of the line
     ///
-    /// This panics if we can't decrement.
-    #[expect(
-        clippy::cast_possible_truncation,
-        clippy::as_conversions,
-        reason = "//TODO"
-    )]
-    pub fn update_cursor(&self) -> IoResult {
-        stdout()
-            .execute(MoveToColumn(PREFIX_SIZE.saturating_add(self.cursor as u16)))
-            .map(|_| ())
+    /// In dumb rendering, the text before the cursor on its row is printed
+    /// again instead of moving the cursor, after the row if the cursor moved
+    /// to another one.
+    pub fn update_cursor(&mut self, terminal: &mut dyn Terminal) -> IoResult {
+        let before = self.before_cursor();
+        let row = before.matches('\n').count();
+        let before_row = before.rsplit('\n').next().unwrap_or_default();
+        if self.rendering == Rendering::Dumb {
+            let text = format!("\r{}{before_row}", self.row_prompt(row));
+            if row != self.cursor_row {
+                self.print_row(terminal, "")?;
+            }
+            return terminal.write(&text);
+        }
+        let mut widths = self.widths("");
+        widths.truncate(row);
+        widths.push(
+            self.row_prompt(row)
+                .chars()
+                .count()
+                .saturating_add(before_row.chars().count()),
+        );
+        let (screen_row, column) = end_position(&widths, columns(terminal));
+        self.move_to_row(terminal, screen_row)?;
+        terminal.move_to_column(u16::try_from(column).unwrap_or(u16::MAX))
+    }
+
+    /// Widths of the rows of the line with their prompts, followed by a hint
+    fn widths(&self, hint: &str) -> Vec<usize> {
+        let mut widths: Vec<_> = self
+            .content
+            .split('\n')
+            .enumerate()
+            .map(|(row, text)| {
+                self.row_prompt(row)
+                    .chars()
+                    .count()
+                    .saturating_add(text.chars().count())
+            })
+            .collect();
+        if let Some(last) = widths.last_mut() {
+            *last = last.saturating_add(hint.chars().count());
+        }
+        widths
+    }
+
+    /// Insert the last killed text at the cursor
+    pub fn yank(&mut self, terminal: &mut dyn Terminal) -> IoResult {
+        let killed = self.killed.clone();
+        self.insert_str(&killed);
+        self.redraw(terminal)
+    }
+}
+
+impl Default for Line {
+    fn default() -> Self {
+        Self::from(String::new())
+    }
+}
+
+impl From<String> for Line {
+    fn from(content: String) -> Self {
+        let cursor = content.len();
+        Self {
+            color: false,
+            content,
+            continuation: DEFAULT_CONTINUATION.to_owned(),
+            cursor,
+            cursor_row: 0,
+            drawn: 0,
+            highlighted_cursor: None,
+            highlighter: None,
+            killed: String::new(),
+            prompt: DEFAULT_PROMPT.to_owned(),
+            rendering: Rendering::Full,
+        }
     }
 }
 
@@ -89,3 +607,149 @@ impl Deref for Line {
         &self.content
     }
 }
+
+/// Number of columns of the terminal, unlimited if it is unknown
+fn columns(terminal: &dyn Terminal) -> usize {
+    terminal
+        .size()
+        .map_or(usize::MAX, |(columns, _)| usize::from(columns).max(1))
+}
+
+/// Position of the terminal cursor after printing rows of the given widths,
+/// as a row from the first one and a column
+///
+/// A row filling its last column is followed by a new row, as printed by
+/// [`Line::print_hint`].
+fn end_position(widths: &[usize], columns: usize) -> (usize, usize) {
+    let (last, previous) = widths.split_last().unwrap_or((&0, &[]));
+    let row = height(previous, columns);
+    (
+        row.saturating_add(last.checked_div(columns).unwrap_or_default()),
+        last.checked_rem(columns).unwrap_or(*last),
+    )
+}
+
+/// Checks if a row of the given width ends at the last column of the terminal
+const fn fills_rows(width: usize, columns: usize) -> bool {
+    width > 0 && width.is_multiple_of(columns)
+}
+
+/// Number of rows of the terminal taken by rows of the given widths, once
+/// wrapped
+fn height(widths: &[usize], columns: usize) -> usize {
+    widths
+        .iter()
+        .map(|width| width.div_ceil(columns).max(1))
+        .fold(0, usize::saturating_add)
+}
+
+/// Converts a number of rows for the terminal
+fn rows(count: usize) -> u16 {
+    u16::try_from(count).unwrap_or(u16::MAX)
+}
+
+#[cfg(test)]
+#[expect(clippy::inline_modules, clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use super::Line;
+    use crate::terminal::Rendering;
+    use crate::terminal::emulator::VirtualTerminal;
+
+    /// Creates an empty line with the prompt `> `
+    fn line() -> Line {
+        let mut line = Line::default();
+        line.set_prompt("> ".to_owned());
+        line
+    }
+
+    /// Types the text on the line, one character at a time
+    fn type_str(line: &mut Line, terminal: &mut VirtualTerminal, text: &str) {
+        for ch in text.chars() {
+            line.insert(terminal, ch).unwrap();
+        }
+        line.update_cursor(terminal).unwrap();
+    }
+
+    #[test]
+    fn dumb() {
+        let mut terminal = VirtualTerminal::new(20, 4);
+        let mut line = line();
+        line.set_rendering(Rendering::Dumb);
+        type_str(&mut line, &mut terminal, "hello");
+        line.backspace(&mut terminal).unwrap();
+        line.backspace(&mut terminal).unwrap();
+        line.decrease_counter();
+        line.update_cursor(&mut terminal).unwrap();
+        assert_eq!(terminal.screen(), ["> hel", "", "", ""]);
+        assert_eq!(terminal.cursor(), (4, 0));
+        line.insert_str("a\nb");
+        line.redraw(&mut terminal).unwrap();
+        line.update_cursor(&mut terminal).unwrap();
+        assert_eq!(terminal.screen(), ["> hel", "... bl", "", ""]);
+        assert_eq!(terminal.cursor(), (5, 1));
+    }
+
+    #[test]
+    fn edit() {
+        let mut terminal = VirtualTerminal::new(20, 4);
+        let mut line = line();
+        type_str(&mut line, &mut terminal, "hello");
+        assert_eq!(terminal.screen(), ["> hello", "", "", ""]);
+        assert_eq!(terminal.cursor(), (7, 0));
+        line.backspace(&mut terminal).unwrap();
+        line.decrease_counter();
+        line.decrease_counter();
+        line.update_cursor(&mut terminal).unwrap();
+        assert_eq!(terminal.screen(), ["> hell", "", "", ""]);
+        assert_eq!(terminal.cursor(), (4, 0));
+        type_str(&mut line, &mut terminal, "y");
+        line.move_to_start();
+        line.update_cursor(&mut terminal).unwrap();
+        assert_eq!(terminal.screen(), ["> heyll", "", "", ""]);
+        assert_eq!(terminal.cursor(), (2, 0));
+        line.increase_counter();
+        line.delete(&mut terminal).unwrap();
+        line.update_cursor(&mut terminal).unwrap();
+        assert_eq!(terminal.screen(), ["> hyll", "", "", ""]);
+        assert_eq!(terminal.cursor(), (3, 0));
+    }
+
+    #[test]
+    fn multi_line() {
+        let mut terminal = VirtualTerminal::new(20, 4);
+        let mut line = line();
+        type_str(&mut line, &mut terminal, "if x:\n  y\nend");
+        assert_eq!(terminal.screen(), ["> if x:", "...   y", "... end", ""]);
+        assert_eq!(terminal.cursor(), (7, 2));
+        assert!(line.move_up());
+        assert!(line.move_up());
+        line.update_cursor(&mut terminal).unwrap();
+        assert_eq!(terminal.cursor(), (5, 0));
+        line.move_past_end(&mut terminal).unwrap();
+        assert_eq!(terminal.cursor(), (7, 2));
+    }
+
+    #[test]
+    fn wrap() {
+        let mut terminal = VirtualTerminal::new(10, 5);
+        let mut line = line();
+        type_str(&mut line, &mut terminal, "abcdefghijklmnop");
+        assert_eq!(terminal.screen(), ["> abcdefgh", "ijklmnop", "", "", ""]);
+        assert_eq!(terminal.cursor(), (8, 1));
+        line.move_to_start();
+        line.update_cursor(&mut terminal).unwrap();
+        assert_eq!(terminal.cursor(), (2, 0));
+        line.redraw(&mut terminal).unwrap();
+        line.update_cursor(&mut terminal).unwrap();
+        assert_eq!(terminal.screen(), ["> abcdefgh", "ijklmnop", "", "", ""]);
+        assert_eq!(terminal.cursor(), (2, 0));
+        line.move_to_end();
+        type_str(&mut line, &mut terminal, "qr");
+        assert_eq!(terminal.screen(), ["> abcdefgh", "ijklmnopqr", "", "", ""]);
+        assert_eq!(terminal.cursor(), (0, 2));
+        line.backspace(&mut terminal).unwrap();
+        line.update_cursor(&mut terminal).unwrap();
+        assert_eq!(terminal.screen(), ["> abcdefgh", "ijklmnopq", "", "", ""]);
+        assert_eq!(terminal.cursor(), (9, 1));
+    }
+}
@@ -0,0 +1,78 @@
+//! Prints messages above the line being edited, e.g. from other threads
+
+use core::time::Duration;
+use std::io;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Instant;
+
+/// Longest time the messages wait to be printed while a key is read
+const PRINT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Handle to print messages above the line being edited, from any thread
+///
+/// It is obtained with [`App::external_printer`](crate::App::external_printer)
+/// and can be cloned and sent to other threads, e.g. to report the progress
+/// of background jobs. The messages are queued and printed by the runner
+/// while it waits for the keys: the line is cleared, the messages are printed,
+/// and the line is drawn again below them with the cursor in place.
+#[derive(Clone, Debug)]
+pub struct ExternalPrinter {
+    /// Sends the messages to the runner
+    sender: Sender<String>,
+}
+
+impl ExternalPrinter {
+    /// Queues a message to be printed on its own line
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the application was dropped.
+    pub fn print(&self, message: String) -> io::Result<()> {
+        self.sender
+            .send(message)
+            .map_err(|err| io::Error::new(io::ErrorKind::BrokenPipe, err))
+    }
+}
+
+/// Queue of the messages sent by the external printers
+pub struct Messages {
+    /// Receives the messages of the printers
+    receiver: Receiver<String>,
+    /// Kept to create new printers
+    sender: Sender<String>,
+}
+
+impl Messages {
+    /// Creates a printer sending messages to this queue
+    pub fn printer(&self) -> ExternalPrinter {
+        ExternalPrinter { sender: self.sender.clone() }
+    }
+
+    /// Takes the messages sent so far
+    pub fn take(&self) -> Vec<String> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self { receiver, sender }
+    }
+}
+
+/// Translates the line feeds of a text for the raw mode, in which they don't
+/// return to the start of the row
+pub fn raw_text(text: &str) -> String {
+    text.replace('\n', "\r\n")
+}
+
+/// Get how long to wait for a key before checking the messages again
+///
+/// The wait ends at the deadline, if there is one.
+pub fn print_wait(deadline: Option<Instant>) -> Duration {
+    deadline.map_or(PRINT_INTERVAL, |end| {
+        end.saturating_duration_since(Instant::now())
+            .min(PRINT_INTERVAL)
+    })
+}
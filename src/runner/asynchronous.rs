@@ -0,0 +1,119 @@
+//! Runs the application without blocking the thread while waiting for keys
+
+use core::future::{Future, poll_fn};
+use core::panic::AssertUnwindSafe;
+use core::pin::{Pin, pin};
+use core::task::Poll;
+use core::time::Duration;
+use std::panic;
+
+use futures_timer::Delay;
+
+use super::{Action, App, Log, Mode, handler_panic};
+use crate::IoResult;
+use crate::error::Error;
+use crate::interface::AppInterface;
+use crate::key::Key;
+use crate::terminal::Terminal;
+
+/// Mode of the runner waiting for the keys without blocking the thread, with
+/// an asynchronous handler for the submitted lines
+struct NonBlocking<'handler, H>(&'handler mut H);
+
+impl<S: Action, L: Log> App<S, L> {
+    /// Reads a line without blocking the thread, see [`App::readline`]
+    ///
+    /// The keys are read from crossterm's `EventStream`, or from
+    /// [`Terminal::poll_key`](crate::Terminal::poll_key) for a custom
+    /// terminal. The reading stops as soon as the `cancel` future completes,
+    /// e.g. on a shutdown signal.
+    ///
+    /// When the standard input isn't a terminal, the line is read from it
+    /// blocking the thread, and `cancel` has no effect. The same goes for a
+    /// custom terminal which doesn't implement `poll_key`.
+    ///
+    /// The future isn't `Send`, see [`App::run_async`].
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Cancelled`] if `cancel` completed before the line was
+    ///   submitted.
+    /// - The errors of [`App::readline`].
+    pub async fn readline_async<C>(&mut self, prompt: &str, cancel: C) -> Result<String, Error>
+    where
+        C: Future,
+    {
+        let mut on_submit = async |_: &mut AppInterface<'_>| ();
+        self.readline_with(prompt, &mut NonBlocking(&mut on_submit), cancel)
+            .await
+    }
+
+    /// Runs the loop on the line inputs without blocking the thread, see
+    /// [`App::run`]
+    ///
+    /// The `on_submit` handler is executed for every submitted line instead
+    /// of the one of [`App::on_submit`], and can await. The keys are read from
+    /// crossterm's `EventStream`, or from
+    /// [`Terminal::poll_key`](crate::Terminal::poll_key) for a custom
+    /// terminal. The loop stops as soon as the `cancel` future completes, e.g.
+    /// on a shutdown signal, even while the handler is running.
+    ///
+    /// When the standard input isn't a terminal, its lines are read blocking
+    /// the thread, so `cancel` is only checked between them. A custom terminal
+    /// which doesn't implement `poll_key` blocks the same way while waiting for
+    /// a key.
+    ///
+    /// The future isn't `Send`, since the application holds its handlers and
+    /// terminal as non-`Send` values: it can't be given to `tokio::spawn`, but
+    /// it can be awaited on the current task, e.g. in `main` or with a
+    /// `LocalSet`.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Cancelled`] if `cancel` completed before a handler requested
+    ///   to exit.
+    /// - The errors of [`App::run`].
+    pub async fn run_async<H, C>(&mut self, mut on_submit: H, cancel: C) -> Result<i32, Error>
+    where
+        H: AsyncFnMut(&mut AppInterface<'_>),
+        C: Future,
+    {
+        self.run_with(&mut NonBlocking(&mut on_submit), cancel)
+            .await
+    }
+}
+
+impl<H: AsyncFnMut(&mut AppInterface<'_>)> Mode for NonBlocking<'_, H> {
+    async fn call(&mut self, interface: &mut AppInterface<'_>) -> Result<(), Error> {
+        let mut handling = pin!((self.0)(interface));
+        poll_fn(
+            |cx| match panic::catch_unwind(AssertUnwindSafe(|| handling.as_mut().poll(cx))) {
+                Ok(poll) => poll.map(Ok),
+                Err(payload) => Poll::Ready(Err(handler_panic(&*payload))),
+            },
+        )
+        .await
+    }
+
+    async fn read_key(
+        &mut self,
+        terminal: &mut dyn Terminal,
+        timeout: Option<Duration>,
+    ) -> IoResult<Option<Key>> {
+        let mut delay = timeout.map(Delay::new);
+        poll_fn(|cx| {
+            if let Poll::Ready(read) = terminal.poll_key(cx) {
+                return Poll::Ready(read.map(Some));
+            }
+            if delay
+                .as_mut()
+                .is_some_and(|elapsed| Pin::new(elapsed).poll(cx).is_ready())
+            {
+                Poll::Ready(Ok(None))
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
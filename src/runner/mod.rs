@@ -0,0 +1,1416 @@
+//! Runner for the application
+
+#![expect(
+    clippy::future_not_send,
+    reason = "the application isn't `Send`, e.g. its handlers and terminal"
+)]
+
+#[cfg(feature = "async")]
+mod asynchronous;
+
+use core::any::Any;
+use core::future::{Future, pending, poll_fn};
+use core::panic::AssertUnwindSafe;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+use core::time::Duration;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use std::{fs, io, panic};
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use crossterm::terminal::ClearType;
+
+use crate::command::{Command, Named, Registry};
+use crate::diagnostics::LogEvent;
+use crate::error::Error;
+use crate::highlight::Highlighter;
+use crate::history::expansion;
+use crate::history::namespaces::Histories;
+use crate::inputrc::{self, Entry, Variable};
+use crate::interface::{AppInterface, ReturnStatus};
+use crate::key::{Binding, DisplaySequence, Key, KeyConflict, KeyPress, Keys, Resolution};
+use crate::line::Line;
+use crate::printer::{ExternalPrinter, Messages, print_wait, raw_text};
+use crate::settings::Settings;
+use crate::terminal::{CrosstermTerminal, Rendering, Terminal, detect_color};
+use crate::validate::{Validation, Validator};
+use crate::{History, HistoryBackend, IoResult};
+
+/// Log the error if it exists
+///
+/// The error is logged in the way specified by the user. If no way was
+/// provided, nothing is done.
+macro_rules! log_error {
+    ($self:ident, $result:expr) => {
+        match $result {
+            Ok(val) => Some(val),
+            Err(err) => {
+                $self.report(&LogEvent::Error(Error::from(err)));
+                None
+            }
+        }
+    };
+}
+
+/// Exit code when the runner is interrupted, like a process killed by SIGINT
+const INTERRUPT_EXIT_CODE: i32 = 130;
+
+impl<A> Action for A where A: FnMut(&mut AppInterface<'_>) {}
+
+/// Type of an action
+///
+/// An action is what is executed on every time a key is pressed.
+pub trait Action: FnMut(&mut AppInterface<'_>) {}
+
+/// Application data containing the current line and the history of executed
+/// commands.
+#[expect(clippy::struct_excessive_bools, reason = "independent options")]
+pub struct App<S: Action, L: Log> {
+    /// Commands defined by the user, that can be invoked by name
+    commands: Registry,
+    /// Expand the history references before submitting a line
+    expansion: bool,
+    /// Histories of submitted lines
+    histories: Histories,
+    /// Exit instead of abandoning the line on Ctrl-C
+    interrupt_exits: bool,
+    /// Commands and actions executed when a bound key is pressed
+    keys: Keys,
+    /// Current line
+    line: Line,
+    /// Action executed when an error occurs
+    log: Option<L>,
+    /// Messages queued by the external printers, if one was created
+    messages: Option<Messages>,
+    /// Action executed every line
+    on_submit: Option<S>,
+    /// Whether the pending sequence is currently displayed
+    pending_shown: bool,
+    /// Names of the commands defined by the user being executed, which can't
+    /// be invoked again until they return
+    running: Vec<String>,
+    /// Settings that can be set by the end user
+    settings: Settings,
+    /// Display the pending sequence after the line
+    show_pending: bool,
+    /// Terminal from which the keys are read and on which the line is drawn
+    terminal: Box<dyn Terminal>,
+    /// Decides whether the line is submitted when Enter is pressed
+    validator: Option<Box<dyn Validator>>,
+}
+
+impl<S: Action, L: Log> App<S, L> {
+    /// Execute the actions requested by the handlers, with the handler of
+    /// the mode for the submitted line
+    ///
+    /// Returns the exit code if the runner must stop.
+    async fn apply<M>(
+        &mut self,
+        mut status: ReturnStatus,
+        mode: &mut M,
+    ) -> Result<Option<i32>, Error>
+    where
+        M: Mode,
+    {
+        if status.take_submit() {
+            status.add(self.take_action(mode).await?);
+        }
+        if status.abort() {
+            self.end_line("^C")?;
+            self.reset_line();
+            if self.interrupt_exits {
+                status.add(ReturnStatus::exit(INTERRUPT_EXIT_CODE));
+            } else {
+                self.line.redraw(&mut *self.terminal)?;
+            }
+        }
+        if status.clear_screen() {
+            if self.line.rendering() == Rendering::Dumb {
+                self.terminal.write("\n\r")?;
+            } else {
+                self.terminal.clear(ClearType::All)?;
+                self.terminal.move_to(0, 0)?;
+            }
+            self.line.redraw(&mut *self.terminal)?;
+        }
+        if status.redraw() {
+            self.line.redraw(&mut *self.terminal)?;
+        }
+        if status.suspend() {
+            self.suspend()?;
+        }
+        Ok(status.exit_code())
+    }
+
+    /// Get the history in use, without changing it
+    #[cfg(feature = "testing")]
+    pub(crate) const fn current_history(&self) -> &History {
+        self.histories.current()
+    }
+
+    /// Writes some text after the line, e.g. `^C`, and moves to the next row
+    fn end_line(&mut self, text: &str) -> IoResult {
+        self.line.move_past_end(&mut *self.terminal)?;
+        self.terminal.write(&format!("{text}\n\r"))
+    }
+
+    /// Ends a step of a loop reading the input
+    ///
+    /// Returns the result of the loop if it must stop, i.e. its value or a
+    /// fatal error. The other errors are reported, and the cursor is put back
+    /// in its place for the next step.
+    fn end_step<T>(&mut self, result: Result<Option<T>, Error>) -> Option<Result<T, Error>> {
+        match result {
+            Ok(Some(value)) => return Some(Ok(value)),
+            Ok(None) => (),
+            Err(err) if err.is_fatal() => return Some(Err(err)),
+            Err(err) => self.report(&LogEvent::Error(err)),
+        }
+        self.load_histories();
+        log_error!(self, self.line.refresh(&mut *self.terminal));
+        log_error!(self, self.line.update_cursor(&mut *self.terminal));
+        None
+    }
+
+    /// Execute a built-in editing command
+    fn execute(&mut self, command: Command) -> Result<ReturnStatus, Error> {
+        let history = self.histories.current_mut();
+        let replacement = match command {
+            Command::AcceptLine => return Ok(ReturnStatus::SUBMIT),
+            Command::BackwardChar => {
+                self.line.decrease_counter();
+                None
+            }
+            Command::BackwardDeleteChar => {
+                self.line.backspace(&mut *self.terminal)?;
+                None
+            }
+            Command::BackwardKillLine => {
+                self.line.backward_kill_line(&mut *self.terminal)?;
+                None
+            }
+            Command::BackwardKillWord => {
+                self.line.backward_kill_word(&mut *self.terminal)?;
+                None
+            }
+            Command::BackwardWord => {
+                self.line.backward_word();
+                None
+            }
+            Command::BeginningOfHistory => history.first(),
+            Command::BeginningOfLine => {
+                self.line.move_to_start();
+                None
+            }
+            Command::ClearScreen => return Ok(ReturnStatus::CLEAR_SCREEN),
+            Command::EndOfFile if self.line.is_empty() => return Ok(ReturnStatus::exit(0)),
+            Command::DeleteChar | Command::EndOfFile => {
+                self.line.delete(&mut *self.terminal)?;
+                None
+            }
+            Command::DownLineOrHistory =>
+                if self.line.move_down() {
+                    None
+                } else {
+                    history.down()
+                },
+            Command::EndOfHistory => {
+                history.reset_cursor();
+                Some("")
+            }
+            Command::EndOfLine => {
+                self.line.move_to_end();
+                None
+            }
+            Command::ForwardChar => {
+                self.line.increase_counter();
+                None
+            }
+            Command::ForwardWord => {
+                self.line.forward_word();
+                None
+            }
+            Command::HistorySearchBackward => history.up_starting_with(self.line.before_cursor()),
+            Command::HistorySearchForward => history.down_starting_with(self.line.before_cursor()),
+            Command::Interrupt => return Ok(ReturnStatus::ABORT),
+            Command::KillLine => {
+                self.line.kill_line(&mut *self.terminal)?;
+                None
+            }
+            Command::KillWord => {
+                self.line.kill_word(&mut *self.terminal)?;
+                None
+            }
+            Command::InsertNewline => {
+                self.line.insert(&mut *self.terminal, '\n')?;
+                None
+            }
+            Command::NextHistory => history.down(),
+            Command::PreviousHistory => history.up(),
+            Command::Suspend => return Ok(ReturnStatus::SUSPEND),
+            Command::TransposeChars => {
+                self.line.transpose_chars(&mut *self.terminal)?;
+                None
+            }
+            Command::UpLineOrHistory =>
+                if self.line.move_up() {
+                    None
+                } else {
+                    history.up()
+                },
+            Command::Yank => {
+                self.line.yank(&mut *self.terminal)?;
+                None
+            }
+        };
+        if let Some(line) = replacement {
+            let cursor =
+                matches!(command, Command::HistorySearchBackward | Command::HistorySearchForward)
+                    .then(|| self.line.before_cursor().len());
+            self.line.set(&mut *self.terminal, line.to_owned())?;
+            if let Some(position) = cursor {
+                self.line.set_cursor(position);
+            }
+        }
+        Ok(ReturnStatus::default())
+    }
+
+    /// Execute a command by its name
+    ///
+    /// Unknown commands are logged, as are the commands defined by the user
+    /// invoked again while they run, which would never end.
+    fn execute_named(&mut self, name: &str) -> Result<ReturnStatus, Error> {
+        if self.running.iter().any(|running| running == name) {
+            self.report(&LogEvent::Error(Error::RecursiveCommand(name.to_owned())));
+            return Ok(ReturnStatus::default());
+        }
+        match self.commands.get_mut(name) {
+            Some(Named::Builtin(command)) => self.execute(command),
+            Some(Named::Custom(action)) => {
+                self.running.push(name.to_owned());
+                let mut interface = AppInterface::new(
+                    &mut self.line,
+                    &mut self.histories,
+                    &mut *self.terminal,
+                    true,
+                );
+                let called = call_handler(action, &mut interface)
+                    .and_then(|()| interface.end_output().map_err(Error::from));
+                let requests = (interface.take_status(), interface.take_commands());
+                let executed = called.and_then(|()| self.handled(requests));
+                self.running.pop();
+                executed
+            }
+            None => {
+                self.report(&LogEvent::Error(Error::UnknownCommand(name.to_owned())));
+                Ok(ReturnStatus::default())
+            }
+        }
+    }
+
+    /// Execute what was requested by a handler through the [`AppInterface`]
+    ///
+    /// The requests are the status and the names of the commands to execute.
+    /// The line is redrawn first, as the handler may have modified it.
+    fn handled(
+        &mut self,
+        (mut status, commands): (ReturnStatus, Vec<String>),
+    ) -> Result<ReturnStatus, Error> {
+        self.line.redraw(&mut *self.terminal)?;
+        for name in commands {
+            status.add(self.execute_named(&name)?);
+        }
+        Ok(status)
+    }
+
+    /// Execute what was requested by the handler of a submitted line, unless
+    /// it requested to exit
+    fn handled_submission(
+        &mut self,
+        (mut status, commands): (ReturnStatus, Vec<String>),
+    ) -> Result<ReturnStatus, Error> {
+        if status.exit_code().is_some() {
+            return Ok(status);
+        }
+        self.line.redraw(&mut *self.terminal)?;
+        status.add(self.handled((ReturnStatus::NONE, commands))?);
+        Ok(status)
+    }
+
+    /// Load the history in use from its storage if it wasn't, and report the
+    /// loads of the histories, e.g. the ones switched to by a handler
+    fn load_histories(&mut self) {
+        self.histories.load_current();
+        for outcome in self.histories.take_loads() {
+            let event = match outcome {
+                Ok((name, len)) => LogEvent::HistoryLoaded { name, len },
+                Err(err) => LogEvent::Error(err),
+            };
+            self.report(&event);
+        }
+    }
+
+    /// Handle a pressed key
+    fn press(&mut self, key: Key) -> Result<ReturnStatus, Error> {
+        let status = match self.keys.press(key) {
+            Resolution::Bound(Binding::Command(command)) => {
+                let bound = *command;
+                self.execute(bound)?
+            }
+            Resolution::Bound(Binding::Action(action)) => {
+                let mut interface = AppInterface::new(
+                    &mut self.line,
+                    &mut self.histories,
+                    &mut *self.terminal,
+                    true,
+                );
+                call_handler(action, &mut interface)?;
+                interface.end_output()?;
+                let requests = (interface.take_status(), interface.take_commands());
+                self.handled(requests)?
+            }
+            Resolution::Bound(Binding::Macro(text)) => {
+                let inserted = text.clone();
+                for ch in inserted.chars() {
+                    self.line.insert(&mut *self.terminal, ch)?;
+                }
+                ReturnStatus::default()
+            }
+            Resolution::Bound(Binding::Named(name)) => {
+                let invoked = name.clone();
+                self.execute_named(&invoked)?
+            }
+            Resolution::Pending => ReturnStatus::default(),
+            Resolution::Unbound(keys) => self.press_unbound(keys)?,
+        };
+        self.show_pending()?;
+        Ok(status)
+    }
+
+    /// Handle keys that don't match any binding
+    ///
+    /// The first key is inserted if it is a character, and the following ones
+    /// are pressed again as they may be the start of another sequence. The
+    /// characters typed with Control or Alt aren't inserted, unless both are
+    /// held, as Windows reports `AltGr` this way.
+    fn press_unbound(&mut self, unbound: Vec<Key>) -> Result<ReturnStatus, Error> {
+        let mut keys = unbound.into_iter();
+        if let Some(key) = keys.next() {
+            let held = key.modifiers() & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+            if let KeyCode::Char(ch) = key.code()
+                && (held.is_empty() || held == KeyModifiers::CONTROL | KeyModifiers::ALT)
+            {
+                self.report(&LogEvent::Inserted(ch));
+                self.line.insert(&mut *self.terminal, ch)?;
+            } else {
+                self.report(&LogEvent::Unbound(key));
+            }
+        }
+        let mut status = ReturnStatus::default();
+        for key in keys {
+            status.add(self.press(key)?);
+        }
+        Ok(status)
+    }
+
+    /// Prints the messages queued by the external printers above the line
+    fn print_messages(&mut self) -> IoResult {
+        let printed = self
+            .messages
+            .as_ref()
+            .map(Messages::take)
+            .unwrap_or_default();
+        if printed.is_empty() {
+            return Ok(());
+        }
+        self.line.erase(&mut *self.terminal)?;
+        for message in printed {
+            self.terminal.write(&raw_text(&format!("{message}\n")))?;
+        }
+        self.line.redraw(&mut *self.terminal)?;
+        self.line.update_cursor(&mut *self.terminal)
+    }
+
+    /// Stores a submitted line in the history in use, reporting the errors
+    fn push_history(&mut self, line: String) {
+        let history = self.histories.current_mut();
+        if let Err(err) = history.push(line) {
+            let error = Error::history(history, err);
+            self.report(&LogEvent::Error(error));
+        }
+    }
+
+    /// Reads a key in the given mode, printing the messages of the external
+    /// printers while waiting
+    ///
+    /// Returns `None` if the time to wait elapsed.
+    async fn read_key<M>(
+        &mut self,
+        mode: &mut M,
+        timeout: Option<Duration>,
+    ) -> Result<Option<Key>, Error>
+    where
+        M: Mode,
+    {
+        if self.messages.is_none() {
+            return mode
+                .read_key(&mut *self.terminal, timeout)
+                .await
+                .map_err(Error::EventRead);
+        }
+        let deadline = timeout.and_then(|duration| Instant::now().checked_add(duration));
+        loop {
+            self.print_messages()?;
+            let wait = print_wait(deadline);
+            if let Some(key) = mode
+                .read_key(&mut *self.terminal, Some(wait))
+                .await
+                .map_err(Error::EventRead)?
+            {
+                return Ok(Some(key));
+            }
+            if deadline.is_some_and(|end| Instant::now() >= end) {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Read a line until it is submitted, abandoned or the input ends
+    async fn read_line<M>(&mut self, mode: &mut M) -> Result<String, Error>
+    where
+        M: Mode,
+    {
+        self.load_histories();
+        self.line.redraw(&mut *self.terminal)?;
+        loop {
+            let result = match self.step(mode).await {
+                Ok(status) => self.read_status(status, mode).await,
+                Err(err) => Err(err),
+            };
+            if let Some(line) = self.end_step(result) {
+                return line;
+            }
+        }
+    }
+
+    /// Read the next line of the standard input, when it isn't a terminal,
+    /// joined with the following ones while it is incomplete
+    ///
+    /// Returns `None` at the end of the input.
+    fn read_piped(&self) -> Result<Option<String>, Error> {
+        let Some(mut line) = Self::read_piped_line()? else {
+            return Ok(None);
+        };
+        while self.validation(&line) == Validation::Incomplete
+            && let Some(next) = Self::read_piped_line()?
+        {
+            line.push('\n');
+            line.push_str(&next);
+        }
+        Ok(Some(line))
+    }
+
+    /// Read the next row of the standard input, when it isn't a terminal
+    ///
+    /// Returns `None` at the end of the input.
+    fn read_piped_line() -> Result<Option<String>, Error> {
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line).map_err(Error::EventRead)? == 0 {
+            return Ok(None);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+
+    /// Execute the actions requested by the handlers while reading a line
+    ///
+    /// Returns the line if it was submitted.
+    async fn read_status<M>(
+        &mut self,
+        mut status: ReturnStatus,
+        mode: &mut M,
+    ) -> Result<Option<String>, Error>
+    where
+        M: Mode,
+    {
+        if status.take_submit()
+            && let Some(line) = self.take_submitted()?
+        {
+            self.push_history(line.clone());
+            return Ok(Some(line));
+        }
+        if status.abort() {
+            self.end_line("^C")?;
+            self.reset_line();
+            return Err(Error::Interrupted);
+        }
+        if status.exit_code().is_some() {
+            self.end_line("")?;
+            self.reset_line();
+            return Err(Error::Eof);
+        }
+        self.apply(status, mode).await?;
+        Ok(None)
+    }
+
+    /// Reads the next line of the standard input, when it isn't a terminal,
+    /// and stores it in the history
+    fn readline_piped(&mut self) -> Result<String, Error> {
+        let line = self.read_piped()?.ok_or(Error::Eof)?;
+        self.push_history(line.clone());
+        Ok(line)
+    }
+
+    /// Reads a line in the given mode, until it is submitted or `cancel`
+    /// completes, see [`App::readline`]
+    async fn readline_with<M, C>(
+        &mut self,
+        prompt: &str,
+        mode: &mut M,
+        cancel: C,
+    ) -> Result<String, Error>
+    where
+        M: Mode,
+        C: Future,
+    {
+        if !self.terminal.is_interactive() {
+            return self.readline_piped();
+        }
+        let previous = self.line.set_prompt(prompt.to_owned());
+        let line = self
+            .with_raw_mode(async |app| app.read_line(mode).await, cancel)
+            .await;
+        self.line.set_prompt(previous);
+        line
+    }
+
+    /// Handle a key read from the terminal, or the end of the time to wait
+    /// for the next key of a sequence if there is none
+    fn received(&mut self, read: Option<Key>) -> Result<ReturnStatus, Error> {
+        let Some(key) = read else {
+            let keys = self.keys.flush();
+            self.report(&LogEvent::SequenceTimedOut(keys.clone()));
+            let status = self.press_unbound(keys)?;
+            self.show_pending()?;
+            return Ok(status);
+        };
+        self.report(&LogEvent::KeyPressed(key));
+        self.press(key)
+    }
+
+    /// Log an event in a way wanted by the user not to pollute the terminal
+    ///
+    /// With the `log` or `tracing` features, the event is also forwarded to
+    /// the corresponding facade.
+    fn report(&mut self, event: &LogEvent) {
+        #[cfg(feature = "log")]
+        event.emit_log();
+        #[cfg(feature = "tracing")]
+        event.emit_tracing();
+        if let Some(log) = &mut self.log {
+            log(event);
+        }
+    }
+
+    /// Abandon the line being edited
+    fn reset_line(&mut self) {
+        self.line.take();
+        self.keys.flush();
+        self.histories.current_mut().reset_cursor();
+    }
+
+    /// Disables the raw mode after reading the input
+    ///
+    /// The cursor is moved back to the start of the row, as the line may have
+    /// been left there.
+    fn restore_terminal(&mut self) -> IoResult {
+        self.terminal.write("\r")?;
+        self.terminal.flush()?;
+        self.terminal.disable_raw_mode()
+    }
+
+    /// Executes the action for each line typed on the terminal, until a
+    /// handler requests to exit
+    async fn run_interactive<M>(&mut self, mode: &mut M) -> Result<i32, Error>
+    where
+        M: Mode,
+    {
+        log_error!(self, self.line.redraw(&mut *self.terminal));
+        loop {
+            let result = match self.step(mode).await {
+                Ok(status) => self.apply(status, mode).await,
+                Err(err) => Err(err),
+            };
+            if let Some(code) = self.end_step(result) {
+                return code;
+            }
+        }
+    }
+
+    /// Executes the action for each line of the standard input, when it isn't
+    /// a terminal
+    ///
+    /// Stops at the end of the input, with the code `0`, or when a handler
+    /// requests to exit. The commands requested by the handler are ignored,
+    /// as there is no line being edited, see [`AppInterface::run_command`].
+    async fn run_piped<M>(&mut self, mode: &mut M) -> Result<i32, Error>
+    where
+        M: Mode,
+    {
+        while let Some(line) = self.read_piped()? {
+            let (status, _) = self.submit(line, mode).await?;
+            self.load_histories();
+            if let Some(code) = status.exit_code() {
+                return Ok(code);
+            }
+        }
+        Ok(0i32)
+    }
+
+    /// Runs the loop on the line inputs in the given mode, until a handler
+    /// requests to exit or `cancel` completes, see [`App::run`]
+    async fn run_with<M, C>(&mut self, mode: &mut M, cancel: C) -> Result<i32, Error>
+    where
+        M: Mode,
+        C: Future,
+    {
+        self.report(&LogEvent::Started);
+        self.load_histories();
+        let result = if self.terminal.is_interactive() {
+            self.with_raw_mode(async |app| app.run_interactive(mode).await, cancel)
+                .await
+        } else {
+            until(self.run_piped(mode), cancel).await
+        };
+        self.sync_histories();
+        result
+    }
+
+    /// Displays the pending sequence after the line, if enabled
+    fn show_pending(&mut self) -> IoResult {
+        if self.show_pending {
+            let pending = self.keys.pending();
+            if !pending.is_empty() {
+                self.line
+                    .print_hint(&mut *self.terminal, &format!(" {}-", DisplaySequence(pending)))?;
+                self.pending_shown = true;
+            } else if self.pending_shown {
+                self.line.print_hint(&mut *self.terminal, "")?;
+                self.pending_shown = false;
+            }
+        }
+        Ok(())
+    }
+
+    /// Main runner for one line.
+    async fn step<M>(&mut self, mode: &mut M) -> Result<ReturnStatus, Error>
+    where
+        M: Mode,
+    {
+        let timeout = self.keys.pending_timeout();
+        let read = self.read_key(mode, timeout).await?;
+        self.received(read)
+    }
+
+    /// Stores a submitted line in the history, unless its handler requested
+    /// to exit
+    ///
+    /// Returns the requests of the handler, without the submission.
+    fn store_submitted(
+        &mut self,
+        line: String,
+        (mut status, commands): (ReturnStatus, Vec<String>),
+    ) -> (ReturnStatus, Vec<String>) {
+        status.take_submit();
+        if status.exit_code().is_none() {
+            self.push_history(line);
+        }
+        (status, commands)
+    }
+
+    /// Execute the handler of the mode for a submitted line and store it in
+    /// the history
+    ///
+    /// The line isn't stored if the handler requested to exit. Returns the
+    /// requests of the handler, see [`App::handled`].
+    async fn submit<M>(
+        &mut self,
+        line: String,
+        mode: &mut M,
+    ) -> Result<(ReturnStatus, Vec<String>), Error>
+    where
+        M: Mode,
+    {
+        let mut submitted = Line::from(line);
+        let mut interface =
+            AppInterface::new(&mut submitted, &mut self.histories, &mut *self.terminal, false);
+        mode.call(&mut interface).await?;
+        interface.end_output()?;
+        let requests = (interface.take_status(), interface.take_commands());
+        Ok(self.store_submitted(submitted.take(), requests))
+    }
+
+    /// Persists the changes of the histories, reporting the errors
+    fn sync_histories(&mut self) {
+        if let Err(err) = self.histories.sync() {
+            self.report(&LogEvent::Error(err));
+        }
+    }
+
+    /// Execute the handler of the mode for the submitted line
+    async fn take_action<M>(&mut self, mode: &mut M) -> Result<ReturnStatus, Error>
+    where
+        M: Mode,
+    {
+        let Some(line) = self.take_submitted()? else {
+            return Ok(ReturnStatus::NONE);
+        };
+        self.terminal.disable_raw_mode()?;
+        let submitted = self.submit(line, mode).await;
+        self.terminal.enable_raw_mode()?;
+        self.handled_submission(submitted?)
+    }
+
+    /// Take the submitted line, with the history references expanded if
+    /// enabled
+    ///
+    /// Returns `None` if the line can't be submitted yet, after updating it:
+    /// a newline is inserted if it is incomplete, and an error is shown below
+    /// it if it is invalid or its references can't be expanded.
+    fn take_submitted(&mut self) -> IoResult<Option<String>> {
+        match self.validation(&self.line) {
+            Validation::Complete => (),
+            Validation::Incomplete => {
+                self.line.insert(&mut *self.terminal, '\n')?;
+                return Ok(None);
+            }
+            Validation::Invalid(message) => {
+                self.line.show_error(&mut *self.terminal, &message)?;
+                return Ok(None);
+            }
+        }
+        let expanded = if self.expansion {
+            match expansion::expand(self.histories.current(), &self.line) {
+                Ok(expanded) => expanded,
+                Err(err) => {
+                    let message = err.to_string();
+                    self.report(&LogEvent::Error(err.into()));
+                    self.line.show_error(&mut *self.terminal, &message)?;
+                    return Ok(None);
+                }
+            }
+        } else {
+            None
+        };
+        self.end_line("")?;
+        let line = self.line.take();
+        let Some(echoed) = expanded else {
+            return Ok(Some(line));
+        };
+        self.terminal.write(&format!("{echoed}\n\r"))?;
+        Ok(Some(echoed))
+    }
+
+    /// Validates a line with the validator, if there is one
+    fn validation(&self, line: &str) -> Validation {
+        self.validator
+            .as_ref()
+            .map_or(Validation::Complete, |validator| validator.validate(line))
+    }
+
+    /// Reads the input with the raw mode enabled, until it is done or
+    /// `cancel` completes, restoring the terminal afterwards
+    ///
+    /// The line being edited is abandoned if the input is cancelled.
+    async fn with_raw_mode<T, R, C>(&mut self, read: R, cancel: C) -> Result<T, Error>
+    where
+        R: AsyncFnOnce(&mut Self) -> Result<T, Error>,
+        C: Future,
+    {
+        self.terminal.enable_raw_mode()?;
+        let result = until(read(self), cancel).await;
+        if matches!(result, Err(Error::Cancelled)) {
+            self.end_line("")?;
+            self.reset_line();
+        }
+        let restored = self.restore_terminal();
+        let value = result?;
+        restored?;
+        Ok(value)
+    }
+}
+
+impl<S: Action, L: Log> App<S, L> {
+    /// Binds a key to a built-in editing command
+    ///
+    /// This overrides the previous binding of the key, including the default
+    /// ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key is the start of a bound sequence.
+    pub fn bind(&mut self, key: Key, command: Command) -> Result<(), KeyConflict> {
+        self.keys
+            .define_key(key, Binding::Command(command))
+            .map(|_| ())
+    }
+
+    /// Binds a key to a command invoked by its name
+    ///
+    /// The command can be built-in, e.g. `kill-word`, or defined with
+    /// [`App::define_command`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key is the start of a bound sequence.
+    pub fn bind_named(&mut self, key: Key, name: &str) -> Result<(), KeyConflict> {
+        self.keys
+            .define_key(key, Binding::Named(name.to_owned()))
+            .map(|_| ())
+    }
+
+    /// Binds a sequence of keys to a built-in editing command
+    ///
+    /// The command is executed when all the keys of the sequence are pressed
+    /// one after the other, e.g. `C-x C-e`. This overrides the previous binding
+    /// of the sequence.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sequence is the start of a bound sequence or
+    /// the other way round.
+    pub fn bind_sequence(
+        &mut self,
+        sequence: Vec<Key>,
+        command: Command,
+    ) -> Result<(), KeyConflict> {
+        self.keys
+            .define_sequence(sequence, Binding::Command(command))
+            .map(|_| ())
+    }
+
+    /// Enables or disables the colors
+    ///
+    /// By default, they are disabled if the `NO_COLOR` environment variable
+    /// is set or in dumb rendering, see [`Rendering`].
+    pub const fn color(&mut self, enabled: bool) {
+        self.line.set_color(enabled);
+    }
+
+    /// Iterates over the names of all the commands, built-in or defined by
+    /// the user
+    pub fn command_names(&self) -> impl Iterator<Item = &str> {
+        self.commands.names()
+    }
+
+    /// Sets the text displayed before the following rows of a multi-line
+    /// line, `... ` by default
+    ///
+    /// See [`App::validator`] to continue the line when Enter is pressed.
+    pub fn continuation_prompt(&mut self, prompt: &str) {
+        self.line.set_continuation(prompt.to_owned());
+    }
+
+    /// Defines a command that can be invoked by name
+    ///
+    /// The command can then be bound to keys with [`App::bind_named`], in an
+    /// inputrc file, or executed from a handler with
+    /// [`AppInterface::run_command`]. The built-in commands can't be
+    /// overridden.
+    pub fn define_command(&mut self, name: &str, action: KeyPress) {
+        self.commands.define(name.to_owned(), action);
+    }
+
+    /// Exit instead of abandoning the line when Ctrl-C is pressed
+    ///
+    /// [`App::run`] then returns `130`, the code of a shell command
+    /// interrupted by Ctrl-C. This applies to the `interrupt` command, so to
+    /// any key bound to it, and to [`AppInterface::abort`]. It has no effect
+    /// on [`App::readline`], which returns [`Error::Interrupted`].
+    pub const fn exit_on_interrupt(&mut self, exit: bool) {
+        self.interrupt_exits = exit;
+    }
+
+    /// Creates a handle to print messages above the line being edited, e.g.
+    /// from other threads
+    ///
+    /// The messages are printed while the runner waits for the keys, which
+    /// are then read by slices of 50 milliseconds.
+    pub fn external_printer(&mut self) -> ExternalPrinter {
+        self.messages.get_or_insert_default().printer()
+    }
+
+    /// Highlights the line being edited, e.g. its keywords or the brackets
+    /// matching the one at the cursor
+    ///
+    /// The line is highlighted only if the colors are enabled, see
+    /// [`App::color`]. See [`Tokens`](crate::Tokens) and
+    /// [`MatchingBrackets`](crate::MatchingBrackets) for the built-in
+    /// highlighters.
+    pub fn highlighter<H>(&mut self, highlighter: H)
+    where
+        H: Highlighter + 'static,
+    {
+        self.line.set_highlighter(Box::new(highlighter));
+    }
+
+    /// Stores the history of entered commands
+    ///
+    /// This allows the user to go back in history even after the program is
+    /// killed. This is possible by storing the history of entered commands in a
+    /// file (the same principle as the `.bash_history` file).
+    pub fn history(&mut self, path: String) {
+        if let Err(source) = self.histories.current_mut().store(path.clone()) {
+            let error = Error::History { path: Some(PathBuf::from(path)), source };
+            self.report(&LogEvent::Error(error));
+        }
+    }
+
+    /// Stores the history of entered commands in a custom storage
+    ///
+    /// See [`HistoryBackend`] to store the history elsewhere than in a file.
+    pub fn history_backend<B>(&mut self, backend: B)
+    where
+        B: HistoryBackend + 'static,
+    {
+        self.histories.current_mut().backend(backend);
+    }
+
+    /// Enables bash-style history expansion
+    ///
+    /// When enabled, references like `!!`, `!n`, `!-n`, `!prefix`, `!$` or
+    /// `^old^new` are replaced in the submitted line by the matching entries of
+    /// the history. The expanded line is echoed and stored in the history. If
+    /// a reference doesn't match any entry, the error is logged and shown
+    /// below the line, which isn't submitted and can be edited.
+    pub const fn history_expansion(&mut self, enabled: bool) {
+        self.expansion = enabled;
+    }
+
+    /// Get the history of the submitted lines
+    ///
+    /// It can be used to read, search, pre-seed or edit the history. This is
+    /// the history in use, see [`App::switch_history`].
+    pub const fn history_mut(&mut self) -> &mut History {
+        self.histories.current_mut()
+    }
+
+    /// Get the history with the given name, creating it if it doesn't exist
+    ///
+    /// Every context of the application (e.g. every sub-mode of a tool) can
+    /// have its own history, with its own storage and settings. The history in
+    /// use can be changed with [`App::switch_history`] or
+    /// [`AppInterface::switch_history`], and is loaded from its storage the
+    /// first time it is used.
+    pub fn history_namespace(&mut self, name: &str) -> &mut History {
+        self.histories.namespace(name)
+    }
+
+    /// Loads the key bindings and settings from an inputrc-like file
+    ///
+    /// See [`App::parse_inputrc`] for the syntax. The errors, for instance an
+    /// unknown command or a missing file, are logged.
+    pub fn load_inputrc<P>(&mut self, path: P)
+    where
+        P: AsRef<Path>,
+    {
+        match fs::read_to_string(&path) {
+            Ok(content) => self.parse_inputrc(&content),
+            Err(source) => {
+                let error = Error::ConfigRead { path: path.as_ref().to_path_buf(), source };
+                self.report(&LogEvent::Error(error));
+            }
+        }
+    }
+
+    /// Sets the logger of the app
+    ///
+    /// It receives the errors after which the input goes on and the
+    /// diagnostics of the runner, each with a [`Level`](crate::Level). The
+    /// `log` and `tracing` features forward the same events to these facades,
+    /// without needing a logger.
+    pub fn log(&mut self, log: L) {
+        self.log = Some(log);
+    }
+
+    /// Creates an empty [`App`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the action of a key
+    ///
+    /// This overrides the previous binding of the key, including the default
+    /// ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key is the start of a bound sequence.
+    pub fn on(&mut self, key: Key, on_submit: KeyPress) -> Result<(), KeyConflict> {
+        self.keys
+            .define_key(key, Binding::Action(on_submit))
+            .map(|_| ())
+    }
+
+    /// Sets the action of a sequence of keys
+    ///
+    /// The action is executed when all the keys of the sequence are pressed
+    /// one after the other, e.g. `C-x C-e`. This overrides the previous binding
+    /// of the sequence.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sequence is the start of a bound sequence or
+    /// the other way round.
+    pub fn on_sequence(&mut self, sequence: Vec<Key>, action: KeyPress) -> Result<(), KeyConflict> {
+        self.keys
+            .define_sequence(sequence, Binding::Action(action))
+            .map(|_| ())
+    }
+
+    /// Sets the action of the app
+    pub fn on_submit(&mut self, on_submit: S) {
+        self.on_submit = Some(on_submit);
+    }
+
+    /// Loads the key bindings and settings from the content of an
+    /// inputrc-like file
+    ///
+    /// The syntax is the same as the one of readline's `.inputrc`: each line is
+    /// a comment starting with `#`, a variable assignment like `set
+    /// editing-mode vi`, or a binding like `Control-u: beginning-of-line`,
+    /// `"\C-x\C-e": end-of-line` or `"\ep": "print "`. The supported
+    /// variables are `editing-mode`, `completion-ignore-case`, `history-size`
+    /// and `keyseq-timeout`. The bad lines are logged and ignored.
+    ///
+    /// `editing-mode` and `completion-ignore-case` don't change the editing:
+    /// they are only stored in the [`App::settings`], for the application to
+    /// adapt its own bindings and completion. The key bindings stay emacs-like
+    /// in vi mode.
+    pub fn parse_inputrc(&mut self, content: &str) {
+        for result in inputrc::parse(content, self.settings.editing_mode()) {
+            match result {
+                Ok((_, Entry::Set(variable))) => self.set_variable(variable),
+                Ok((line, Entry::Bind(_, Binding::Named(name))))
+                    if !self.commands.contains(&name) =>
+                {
+                    let message = format!("unknown command `{name}`");
+                    self.report(&LogEvent::Error(Error::Config { line, message }));
+                }
+                Ok((line, Entry::Bind(sequence, binding))) => {
+                    if let Err(err) = self.keys.define_sequence(sequence, binding) {
+                        let message = err.to_string();
+                        self.report(&LogEvent::Error(Error::Config { line, message }));
+                    }
+                }
+                Err(err) => self.report(&LogEvent::Error(err)),
+            }
+        }
+    }
+
+    /// Sets the text displayed at the start of every line by [`App::run`]
+    ///
+    /// The default prompt is `>>> `.
+    pub fn prompt(&mut self, prompt: &str) {
+        self.line.set_prompt(prompt.to_owned());
+    }
+
+    /// Reads a line, displaying the given prompt before it
+    ///
+    /// This uses the same key bindings and history as [`App::run`], but lets
+    /// the caller handle the line instead of [`App::on_submit`]. The submitted
+    /// line is added to the history in use.
+    ///
+    /// If the standard input isn't a terminal, its next line is returned
+    /// without displaying the prompt.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Interrupted`] if the line was abandoned, e.g. with Ctrl-C.
+    /// - [`Error::Eof`] if the end of the input was reached, e.g. with Ctrl-D
+    ///   on an empty line, or if a handler requested to exit.
+    /// - [`Error::Terminal`] if the terminal couldn't be set up.
+    /// - [`Error::EventRead`] if the terminal couldn't be read.
+    /// - [`Error::HandlerPanic`] if a handler panicked.
+    pub fn readline(&mut self, prompt: &str) -> Result<String, Error> {
+        let mut mode = Blocking(&mut |_: &mut AppInterface<'_>| ());
+        block_on(self.readline_with(prompt, &mut mode, pending::<()>()))
+    }
+
+    /// Sets how the line is drawn on the terminal
+    ///
+    /// By default, the rendering is detected from the environment, see
+    /// [`Rendering::detect`]. This doesn't change whether colors are used, see
+    /// [`App::color`].
+    pub const fn rendering(&mut self, rendering: Rendering) {
+        self.line.set_rendering(rendering);
+    }
+
+    /// Run the infinite loop on the line inputs
+    ///
+    /// - On enter press, execute the line.
+    /// - On Ctrl-D on an empty line, exit the runner with the code `0`.
+    /// - On Ctrl-C, abandon the line, or exit the runner with the code `130` if
+    ///   enabled, see [`App::exit_on_interrupt`].
+    ///
+    /// Returns the exit code requested by the handler that stopped the runner,
+    /// see [`AppInterface::exit_with_code`].
+    ///
+    /// If the standard input isn't a terminal, e.g. when the commands are
+    /// piped from a file, the handler is executed for each of its lines
+    /// instead, and `0` is returned at the end of the input.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Terminal`] if the terminal couldn't be set up.
+    /// - [`Error::EventRead`] if the terminal couldn't be read.
+    /// - [`Error::HandlerPanic`] if a handler panicked.
+    pub fn run(&mut self) -> Result<i32, Error> {
+        self.run_wrapped(|interface, on_submit| {
+            if let Some(action) = on_submit {
+                action(interface);
+            }
+        })
+    }
+
+    /// Runs the loop on the line inputs, calling the wrapper for every
+    /// submitted line with the handler of [`App::on_submit`] if there is one
+    ///
+    /// The test harness records the submitted lines this way.
+    pub(crate) fn run_wrapped<W>(&mut self, mut wrapper: W) -> Result<i32, Error>
+    where
+        W: FnMut(&mut AppInterface<'_>, Option<&mut S>),
+    {
+        let mut on_submit = self.on_submit.take();
+        let mut handler = |interface: &mut AppInterface<'_>| wrapper(interface, on_submit.as_mut());
+        let result = block_on(self.run_with(&mut Blocking(&mut handler), pending::<()>()));
+        self.on_submit = on_submit;
+        result
+    }
+
+    /// Sets the time to wait for the next key of a sequence
+    ///
+    /// When the time is elapsed, the keys pressed so far are handled as if they
+    /// weren't the start of a sequence: a character is inserted into the line.
+    pub const fn sequence_timeout(&mut self, timeout: Duration) {
+        self.keys.set_timeout(timeout);
+    }
+
+    /// Applies a variable set by the end user
+    fn set_variable(&mut self, variable: Variable) {
+        match variable {
+            Variable::CompletionIgnoreCase(ignore) =>
+                self.settings.set_completion_ignore_case(ignore),
+            Variable::EditingMode(mode) => self.settings.set_editing_mode(mode),
+            Variable::HistorySize(size) => self.histories.current_mut().set_max_len(size),
+            Variable::KeyseqTimeout(timeout) => self.keys.set_timeout(timeout),
+        }
+    }
+
+    /// Get the settings set by the end user, e.g. in an inputrc file
+    #[must_use]
+    pub const fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Displays the keys of the sequence being typed after the line
+    pub const fn show_pending_sequence(&mut self, show: bool) {
+        self.show_pending = show;
+    }
+
+    /// Suspend the process until it is resumed, see [`Terminal::suspend`]
+    ///
+    /// The line is printed again when the process is resumed.
+    fn suspend(&mut self) -> IoResult {
+        self.end_line("")?;
+        self.terminal.suspend()?;
+        self.line.redraw(&mut *self.terminal)
+    }
+
+    /// Change the history in use, creating it if it doesn't exist
+    ///
+    /// The history is loaded from its storage the first time it is used.
+    pub fn switch_history(&mut self, name: &str) {
+        self.histories.switch(name);
+        self.load_histories();
+    }
+
+    /// Sets the terminal from which the keys are read and on which the line
+    /// is drawn
+    ///
+    /// A [`CrosstermTerminal`] is used by default. See
+    /// [`VirtualTerminal`](crate::VirtualTerminal) to run the application
+    /// without a real terminal.
+    pub fn terminal<T>(&mut self, terminal: T)
+    where
+        T: Terminal + 'static,
+    {
+        self.terminal = Box::new(terminal);
+    }
+
+    /// Removes the binding of a key, including the default ones
+    ///
+    /// If the key is a character, it is inserted into the line when pressed.
+    pub fn unbind(&mut self, key: Key) {
+        self.keys.remove(&[key]);
+    }
+
+    /// Removes the binding of a sequence of keys
+    pub fn unbind_sequence(&mut self, sequence: &[Key]) {
+        self.keys.remove(sequence);
+    }
+
+    /// Checks if colors are used, e.g. to color the output of the handlers
+    /// in the same way
+    #[must_use]
+    pub const fn uses_color(&self) -> bool {
+        self.line.color()
+    }
+
+    /// Decides whether the line is submitted when Enter is pressed, or goes
+    /// on with a newline, e.g. for multi-line function definitions
+    ///
+    /// See [`Balanced`](crate::Balanced) and
+    /// [`TrailingBackslash`](crate::TrailingBackslash) for the built-in
+    /// validators.
+    pub fn validator<V>(&mut self, validator: V)
+    where
+        V: Validator + 'static,
+    {
+        self.validator = Some(Box::new(validator));
+    }
+}
+
+impl<S: Action, L: Log> Default for App<S, L> {
+    fn default() -> Self {
+        let rendering = Rendering::detect();
+        let mut line = Line::default();
+        line.set_rendering(rendering);
+        line.set_color(detect_color(rendering));
+        Self {
+            on_submit: None,
+            commands: Registry::default(),
+            expansion: false,
+            interrupt_exits: false,
+            keys: Keys::new(),
+            histories: Histories::default(),
+            line,
+            log: None,
+            messages: None,
+            pending_shown: false,
+            running: Vec::new(),
+            settings: Settings::default(),
+            show_pending: false,
+            terminal: Box::new(CrosstermTerminal::default()),
+            validator: None,
+        }
+    }
+}
+
+/// Mode of the runner blocking the thread, with a synchronous handler for
+/// the submitted lines
+struct Blocking<'handler, H>(&'handler mut H);
+
+impl<H: Action> Mode for Blocking<'_, H> {
+    async fn call(&mut self, interface: &mut AppInterface<'_>) -> Result<(), Error> {
+        call_handler(self.0, interface)
+    }
+
+    async fn read_key(
+        &mut self,
+        terminal: &mut dyn Terminal,
+        timeout: Option<Duration>,
+    ) -> IoResult<Option<Key>> {
+        terminal.read_key(timeout)
+    }
+}
+
+impl<L: FnMut(&LogEvent)> Log for L {}
+
+/// Type of a log
+///
+/// The log is what is executed in case of error. This allows the users to store
+/// the errors somewhere without killing the program.
+pub trait Log: FnMut(&LogEvent) {}
+
+/// How the runner waits for the keys and calls the handler of the submitted
+/// lines, blocking the thread or not
+///
+/// The loops of the runner are written once for both modes: only these calls
+/// differ.
+trait Mode {
+    /// Calls the handler of a submitted line, catching its panics
+    async fn call(&mut self, interface: &mut AppInterface<'_>) -> Result<(), Error>;
+
+    /// Reads a key, waiting at most for the given duration if there is one
+    ///
+    /// Returns `None` if the time elapsed.
+    async fn read_key(
+        &mut self,
+        terminal: &mut dyn Terminal,
+        timeout: Option<Duration>,
+    ) -> IoResult<Option<Key>>;
+}
+
+/// Runs a future of the blocking mode, which is ready as soon as it is
+/// polled since its reads block the thread
+fn block_on<F>(future: F) -> F::Output
+where
+    F: Future,
+{
+    let mut running = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = running.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+/// Calls a handler, catching its panics
+fn call_handler<A>(handler: &mut A, interface: &mut AppInterface<'_>) -> Result<(), Error>
+where
+    A: Action + ?Sized,
+{
+    panic::catch_unwind(AssertUnwindSafe(|| handler(interface)))
+        .map_err(|payload| handler_panic(&*payload))
+}
+
+/// Creates the error reporting the panic of a handler, with its message
+fn handler_panic(payload: &(dyn Any + Send)) -> Error {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|message| (*message).to_owned())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_default();
+    Error::HandlerPanic(message)
+}
+
+/// Waits for a future, unless `cancel` completes first
+///
+/// # Errors
+///
+/// Returns [`Error::Cancelled`] if `cancel` completed first, or the error of
+/// the future.
+async fn until<T, F, C>(future: F, cancel: C) -> Result<T, Error>
+where
+    F: Future<Output = Result<T, Error>>,
+    C: Future,
+{
+    let mut running = pin!(future);
+    let mut cancelled = pin!(cancel);
+    poll_fn(|cx| {
+        if cancelled.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(Err(Error::Cancelled));
+        }
+        running.as_mut().poll(cx)
+    })
+    .await
+}
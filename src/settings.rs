@@ -0,0 +1,49 @@
+//! Defines the settings of the application that can be set by the end user
+
+/// Editing mode selected by the end user
+///
+/// The default bindings are emacs-like. The mode is made available so that
+/// the application can adapt its own bindings.
+#[non_exhaustive]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EditingMode {
+    /// Emacs editing mode
+    #[default]
+    Emacs,
+    /// Vi editing mode
+    Vi,
+}
+
+/// Settings of the application that can be set by the end user, for instance
+/// in an inputrc file
+#[derive(Default)]
+pub struct Settings {
+    /// Ignore the case of the characters when completing
+    completion_ignore_case: bool,
+    /// Editing mode selected by the end user
+    editing_mode: EditingMode,
+}
+
+impl Settings {
+    /// Checks if the case must be ignored when completing
+    #[must_use]
+    pub const fn completion_ignore_case(&self) -> bool {
+        self.completion_ignore_case
+    }
+
+    /// Get the editing mode selected by the end user
+    #[must_use]
+    pub const fn editing_mode(&self) -> EditingMode {
+        self.editing_mode
+    }
+
+    /// Sets whether the case must be ignored when completing
+    pub const fn set_completion_ignore_case(&mut self, ignore: bool) {
+        self.completion_ignore_case = ignore;
+    }
+
+    /// Sets the editing mode
+    pub const fn set_editing_mode(&mut self, mode: EditingMode) {
+        self.editing_mode = mode;
+    }
+}
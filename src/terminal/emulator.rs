@@ -0,0 +1,282 @@
+//! Emulates a terminal in memory, to run an application without a real one
+
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use core::cell::RefCell;
+#[cfg(feature = "async")]
+use core::task::{Context, Poll};
+use core::time::Duration;
+use std::io;
+
+use crossterm::terminal::ClearType;
+
+use super::Terminal;
+use crate::IoResult;
+use crate::key::Key;
+
+/// Terminal emulated in memory, with scripted keys and a grid of characters
+///
+/// The clones of a [`VirtualTerminal`] share the same state, so one clone can
+/// be given to the [`App`](crate::App) and another one kept to press keys and
+/// inspect the screen, which is useful for tests.
+///
+/// Reading a key when none is left fails with
+/// [`io::ErrorKind::UnexpectedEof`]. If a timeout is given, the time elapses
+/// once instead, e.g. to end a pending sequence.
+#[derive(Clone)]
+pub struct VirtualTerminal {
+    /// State shared between the clones
+    state: Rc<RefCell<State>>,
+}
+
+impl VirtualTerminal {
+    /// Get the position of the cursor, as `(column, row)`
+    #[must_use]
+    pub fn cursor(&self) -> (u16, u16) {
+        let state = self.state.borrow();
+        (state.column, state.row)
+    }
+
+    /// Checks if the raw mode is currently enabled
+    #[must_use]
+    pub fn is_raw_mode(&self) -> bool {
+        self.state.borrow().raw
+    }
+
+    /// Creates an empty terminal of the given size
+    #[must_use]
+    pub fn new(columns: u16, rows: u16) -> Self {
+        let state = State {
+            cells: vec![vec![' '; usize::from(columns)]; usize::from(rows)],
+            column: 0,
+            keys: VecDeque::new(),
+            raw: false,
+            row: 0,
+            timed_out: false,
+        };
+        Self { state: Rc::new(RefCell::new(state)) }
+    }
+
+    /// Adds a key to the ones read by the application, after the previous ones
+    pub fn push_key(&self, key: Key) {
+        self.state.borrow_mut().keys.push_back(key);
+    }
+
+    /// Get the rows displayed on the screen, from top to bottom, without the
+    /// trailing spaces
+    #[must_use]
+    pub fn screen(&self) -> Vec<String> {
+        self.state
+            .borrow()
+            .cells
+            .iter()
+            .map(|row| row.iter().collect::<String>().trim_end().to_owned())
+            .collect()
+    }
+}
+
+impl Default for VirtualTerminal {
+    /// Creates an empty terminal of 80 columns and 24 rows
+    fn default() -> Self {
+        Self::new(80, 24)
+    }
+}
+
+impl Terminal for VirtualTerminal {
+    fn clear(&mut self, clear: ClearType) -> IoResult {
+        self.state.borrow_mut().clear(clear);
+        Ok(())
+    }
+
+    fn disable_raw_mode(&mut self) -> IoResult {
+        self.state.borrow_mut().raw = false;
+        Ok(())
+    }
+
+    fn enable_raw_mode(&mut self) -> IoResult {
+        self.state.borrow_mut().raw = true;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> IoResult {
+        Ok(())
+    }
+
+    fn move_down(&mut self, rows: u16) -> IoResult {
+        let mut state = self.state.borrow_mut();
+        state.row = state
+            .row
+            .saturating_add(rows)
+            .min(state.rows().saturating_sub(1));
+        Ok(())
+    }
+
+    fn move_to(&mut self, column: u16, row: u16) -> IoResult {
+        let mut state = self.state.borrow_mut();
+        state.column = column.min(state.columns().saturating_sub(1));
+        state.row = row.min(state.rows().saturating_sub(1));
+        Ok(())
+    }
+
+    fn move_to_column(&mut self, column: u16) -> IoResult {
+        let mut state = self.state.borrow_mut();
+        state.column = column.min(state.columns().saturating_sub(1));
+        Ok(())
+    }
+
+    fn move_up(&mut self, rows: u16) -> IoResult {
+        let mut state = self.state.borrow_mut();
+        state.row = state.row.saturating_sub(rows);
+        Ok(())
+    }
+
+    #[cfg(feature = "async")]
+    fn poll_key(&mut self, _cx: &mut Context<'_>) -> Poll<IoResult<Key>> {
+        Poll::Ready(
+            self.read_key(None)
+                .and_then(|key| key.ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))),
+        )
+    }
+
+    fn read_key(&mut self, timeout: Option<Duration>) -> IoResult<Option<Key>> {
+        let mut state = self.state.borrow_mut();
+        if let Some(key) = state.keys.pop_front() {
+            state.timed_out = false;
+            Ok(Some(key))
+        } else if timeout.is_some() && !state.timed_out {
+            state.timed_out = true;
+            Ok(None)
+        } else {
+            Err(io::Error::new(io::ErrorKind::UnexpectedEof, "no more keys to read"))
+        }
+    }
+
+    fn size(&self) -> IoResult<(u16, u16)> {
+        let state = self.state.borrow();
+        Ok((state.columns(), state.rows()))
+    }
+
+    fn write(&mut self, text: &str) -> IoResult {
+        let mut state = self.state.borrow_mut();
+        let mut chars = text.chars();
+        while let Some(ch) = chars.next() {
+            if ch == '\x1b' {
+                // Skips the escape sequences, e.g. the styles of a highlighted line
+                if chars.next() == Some('[') {
+                    chars.find(|next| ('@'..='~').contains(next));
+                }
+            } else {
+                state.put(ch);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// State of a [`VirtualTerminal`]
+struct State {
+    /// Characters displayed on the screen, row by row
+    cells: Vec<Vec<char>>,
+    /// Column of the cursor
+    column: u16,
+    /// Keys left to be read
+    keys: VecDeque<Key>,
+    /// Whether the raw mode is enabled
+    raw: bool,
+    /// Row of the cursor
+    row: u16,
+    /// Whether a timeout elapsed since the last key was read
+    timed_out: bool,
+}
+
+impl State {
+    /// Blanks the cells of the given rows, from the given column of the
+    /// first one
+    fn blank(&mut self, rows: impl Iterator<Item = usize>, from: usize) {
+        for (index, row) in rows.enumerate() {
+            if let Some(cells) = self.cells.get_mut(row) {
+                let start = if index == 0 { from } else { 0 };
+                cells.iter_mut().skip(start).for_each(|cell| *cell = ' ');
+            }
+        }
+    }
+
+    /// Clears a part of the screen
+    fn clear(&mut self, clear: ClearType) {
+        let row = usize::from(self.row);
+        let column = usize::from(self.column);
+        match clear {
+            ClearType::All | ClearType::Purge => self.blank(0..self.cells.len(), 0),
+            ClearType::CurrentLine => self.blank(row..=row, 0),
+            ClearType::FromCursorDown => self.blank(row..self.cells.len(), column),
+            ClearType::FromCursorUp => {
+                self.blank(0..row, 0);
+                if let Some(cells) = self.cells.get_mut(row) {
+                    cells
+                        .iter_mut()
+                        .take(column.saturating_add(1))
+                        .for_each(|cell| *cell = ' ');
+                }
+            }
+            ClearType::UntilNewLine => self.blank(row..=row, column),
+        }
+    }
+
+    /// Get the number of columns of the screen
+    fn columns(&self) -> u16 {
+        self.cells
+            .first()
+            .map_or(0, |row| u16::try_from(row.len()).unwrap_or(u16::MAX))
+    }
+
+    /// Moves the cursor to the start of the next row, scrolling the screen if
+    /// the cursor is on the last one
+    fn new_line(&mut self) {
+        self.column = 0;
+        if self.row.saturating_add(1) < self.rows() {
+            self.row = self.row.saturating_add(1);
+        } else if !self.cells.is_empty() {
+            self.cells.remove(0);
+            self.cells.push(vec![' '; usize::from(self.columns())]);
+        }
+    }
+
+    /// Writes a character at the cursor
+    ///
+    /// Carriage returns and line feeds move the cursor, the other control
+    /// characters are ignored. As in a real terminal, a line feed doesn't
+    /// return to the start of the row in raw mode. The text wraps at the end
+    /// of a row.
+    fn put(&mut self, ch: char) {
+        match ch {
+            '\r' => self.column = 0,
+            '\n' => {
+                let column = self.column;
+                self.new_line();
+                if self.raw {
+                    self.column = column;
+                }
+            }
+            _ if ch.is_control() => (),
+            _ => {
+                if self.column >= self.columns() {
+                    self.new_line();
+                }
+                let (row, column) = (usize::from(self.row), usize::from(self.column));
+                if let Some(cell) = self
+                    .cells
+                    .get_mut(row)
+                    .and_then(|cells| cells.get_mut(column))
+                {
+                    *cell = ch;
+                }
+                self.column = self.column.saturating_add(1);
+            }
+        }
+    }
+
+    /// Get the number of rows of the screen
+    fn rows(&self) -> u16 {
+        u16::try_from(self.cells.len()).unwrap_or(u16::MAX)
+    }
+}
@@ -0,0 +1,435 @@
+//! Abstracts the terminal in which the line is edited
+
+pub mod emulator;
+
+#[cfg(feature = "async")]
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "async")]
+use core::task::{Context, Poll, ready};
+use core::time::Duration;
+use std::io::{self, IsTerminal as _, Write as _};
+use std::sync::{Mutex, Once};
+use std::thread::{self, ThreadId};
+use std::time::Instant;
+use std::{env, panic};
+
+use crossterm::QueueableCommand as _;
+use crossterm::cursor::{MoveDown, MoveTo, MoveToColumn, MoveUp};
+#[cfg(feature = "async")]
+use crossterm::event::EventStream;
+use crossterm::event::{Event, KeyEventKind, poll, read};
+use crossterm::terminal::{self, Clear, ClearType};
+#[cfg(feature = "async")]
+use futures_core::Stream as _;
+#[cfg(unix)]
+use signal_hook::{consts::SIGTSTP, low_level::raise};
+
+use crate::IoResult;
+use crate::key::Key;
+
+/// Whether the raw mode is enabled by a [`CrosstermTerminal`]
+static RAW_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Thread that enabled the raw mode last
+static RAW_MODE_THREAD: Mutex<Option<ThreadId>> = Mutex::new(None);
+
+/// Terminal from which the keys are read and on which the line is drawn
+///
+/// [`CrosstermTerminal`] is used by default. Implement this trait to edit the
+/// line elsewhere, e.g. [`VirtualTerminal`](emulator::VirtualTerminal) to
+/// test an application without a real terminal.
+pub trait Terminal {
+    /// Clears a part of the screen, without moving the cursor
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal couldn't be written.
+    fn clear(&mut self, clear: ClearType) -> IoResult;
+
+    /// Restores the terminal, so that the input is line-buffered and echoed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal couldn't be set up.
+    fn disable_raw_mode(&mut self) -> IoResult;
+
+    /// Sets the terminal up to read the keys one by one, without echoing them
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal couldn't be set up.
+    fn enable_raw_mode(&mut self) -> IoResult;
+
+    /// Displays what was written so far
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal couldn't be written.
+    fn flush(&mut self) -> IoResult;
+
+    /// Checks if the keys are typed by a user, or if the input is piped, e.g.
+    /// from a file or another program
+    ///
+    /// When the input isn't interactive, [`App::run`](crate::App::run) and
+    /// [`App::readline`](crate::App::readline) read plain lines from the
+    /// standard input instead of keys, without prompts nor escape codes.
+    fn is_interactive(&self) -> bool {
+        true
+    }
+
+    /// Moves the cursor down by the given number of rows, keeping its column
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal couldn't be written.
+    fn move_down(&mut self, rows: u16) -> IoResult;
+
+    /// Moves the cursor to the given position, starting from the top left
+    /// corner at `(0, 0)`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal couldn't be written.
+    fn move_to(&mut self, column: u16, row: u16) -> IoResult;
+
+    /// Moves the cursor to the given column of the current row, starting
+    /// from 0
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal couldn't be written.
+    fn move_to_column(&mut self, column: u16) -> IoResult;
+
+    /// Moves the cursor up by the given number of rows, keeping its column
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal couldn't be written.
+    fn move_up(&mut self, rows: u16) -> IoResult;
+
+    /// Polls the next pressed key, without blocking, for the asynchronous
+    /// runner
+    ///
+    /// The default implementation blocks until a key is read with
+    /// [`Terminal::read_key`], so the reading can't be cancelled while waiting
+    /// for a key, see [`App::readline_async`](crate::App::readline_async).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal couldn't be read.
+    #[cfg(feature = "async")]
+    fn poll_key(&mut self, _cx: &mut Context<'_>) -> Poll<IoResult<Key>> {
+        Poll::Ready(loop {
+            match self.read_key(None) {
+                Ok(Some(key)) => break Ok(key),
+                Ok(None) => (),
+                Err(err) => break Err(err),
+            }
+        })
+    }
+
+    /// Reads the next pressed key
+    ///
+    /// Waits at most for the given duration if there is one, and forever
+    /// otherwise. Returns `None` if the time elapsed. The other events, e.g.
+    /// resizes, are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal couldn't be read.
+    fn read_key(&mut self, timeout: Option<Duration>) -> IoResult<Option<Key>>;
+
+    /// Get the size of the terminal, as `(columns, rows)`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the size couldn't be read.
+    fn size(&self) -> IoResult<(u16, u16)>;
+
+    /// Suspends the process until it is resumed, like Ctrl-Z in a shell
+    ///
+    /// The default implementation does nothing, so that an emulated terminal
+    /// doesn't stop the process, e.g. in tests.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal couldn't be restored or set up again.
+    fn suspend(&mut self) -> IoResult {
+        Ok(())
+    }
+
+    /// Writes some text at the cursor
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal couldn't be written.
+    fn write(&mut self, text: &str) -> IoResult;
+
+    /// Writes an error message at the cursor
+    ///
+    /// By default, the message is written like any text. Terminals drawing
+    /// on the standard output can write it to the standard error instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminal couldn't be written.
+    fn write_error(&mut self, text: &str) -> IoResult {
+        self.write(text)
+    }
+}
+
+/// How the line is drawn on the terminal
+///
+/// [`Rendering::detect`] is used by default, see
+/// [`App::rendering`](crate::App::rendering) to override it.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rendering {
+    /// Reprints the line without any escape code, for terminals that don't
+    /// support them, e.g. `TERM=dumb`, Emacs shell buffers or serial consoles
+    Dumb,
+    /// Moves the cursor and clears the screen with escape codes
+    Full,
+}
+
+impl Rendering {
+    /// Detects the rendering supported by the terminal from the environment
+    ///
+    /// The rendering is [`Rendering::Dumb`] if `TERM` is `dumb` or if the
+    /// program runs in a shell buffer of Emacs, whose `INSIDE_EMACS` is `t`
+    /// or ends with `,comint`. The terminal emulators of Emacs, e.g. vterm,
+    /// eat or `M-x term`, handle the escape codes.
+    #[must_use]
+    pub fn detect() -> Self {
+        if env::var_os("TERM").is_some_and(|term| term == "dumb")
+            || env::var("INSIDE_EMACS").is_ok_and(|inside| is_comint(&inside))
+        {
+            Self::Dumb
+        } else {
+            Self::Full
+        }
+    }
+}
+
+/// Real terminal, read and written with crossterm through the standard output
+///
+/// The raw mode is disabled when the terminal is dropped, and also when the
+/// program panics, so that the panic message and the shell are readable.
+#[derive(Default)]
+pub struct CrosstermTerminal {
+    /// Stream of the events, for the asynchronous runner
+    #[cfg(feature = "async")]
+    events: Option<EventStream>,
+    /// Whether the raw mode was enabled by this terminal
+    raw: bool,
+}
+
+impl CrosstermTerminal {
+    /// Checks if the raw mode enabled by this terminal is still enabled, as
+    /// the panic hook may have disabled it since
+    fn is_raw(&self) -> bool {
+        self.raw && RAW_MODE.load(Ordering::SeqCst)
+    }
+}
+
+impl Terminal for CrosstermTerminal {
+    fn clear(&mut self, clear: ClearType) -> IoResult {
+        io::stdout().queue(Clear(clear)).map(|_| ())
+    }
+
+    fn disable_raw_mode(&mut self) -> IoResult {
+        if self.is_raw() {
+            terminal::disable_raw_mode()?;
+            RAW_MODE.store(false, Ordering::SeqCst);
+        }
+        self.raw = false;
+        Ok(())
+    }
+
+    fn enable_raw_mode(&mut self) -> IoResult {
+        if !self.is_raw() {
+            install_panic_hook();
+            terminal::enable_raw_mode()?;
+            self.raw = true;
+            if let Ok(mut owner) = RAW_MODE_THREAD.lock() {
+                *owner = Some(thread::current().id());
+            }
+            RAW_MODE.store(true, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> IoResult {
+        io::stdout().flush()
+    }
+
+    fn is_interactive(&self) -> bool {
+        io::stdin().is_terminal()
+    }
+
+    /// Moves the cursor down, as crossterm moves it even by 0 rows
+    fn move_down(&mut self, rows: u16) -> IoResult {
+        if rows == 0 {
+            return Ok(());
+        }
+        io::stdout().queue(MoveDown(rows)).map(|_| ())
+    }
+
+    fn move_to(&mut self, column: u16, row: u16) -> IoResult {
+        io::stdout().queue(MoveTo(column, row)).map(|_| ())
+    }
+
+    fn move_to_column(&mut self, column: u16) -> IoResult {
+        io::stdout().queue(MoveToColumn(column)).map(|_| ())
+    }
+
+    /// Moves the cursor up, as crossterm moves it even by 0 rows
+    fn move_up(&mut self, rows: u16) -> IoResult {
+        if rows == 0 {
+            return Ok(());
+        }
+        io::stdout().queue(MoveUp(rows)).map(|_| ())
+    }
+
+    #[cfg(feature = "async")]
+    fn poll_key(&mut self, cx: &mut Context<'_>) -> Poll<IoResult<Key>> {
+        if let Err(err) = self.flush() {
+            return Poll::Ready(Err(err));
+        }
+        let events = self.events.get_or_insert_with(EventStream::new);
+        loop {
+            match ready!(Pin::new(&mut *events).poll_next(cx)) {
+                Some(Ok(event)) =>
+                    if let Some(key) = pressed_key(&event) {
+                        return Poll::Ready(Ok(key));
+                    },
+                Some(Err(err)) => return Poll::Ready(Err(err)),
+                None => return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into())),
+            }
+        }
+    }
+
+    fn read_key(&mut self, timeout: Option<Duration>) -> IoResult<Option<Key>> {
+        self.flush()?;
+        let deadline = timeout.and_then(|duration| Instant::now().checked_add(duration));
+        loop {
+            if let Some(end) = deadline
+                && !poll(end.saturating_duration_since(Instant::now()))?
+            {
+                return Ok(None);
+            }
+            if let Some(key) = pressed_key(&read()?) {
+                return Ok(Some(key));
+            }
+        }
+    }
+
+    fn size(&self) -> IoResult<(u16, u16)> {
+        terminal::size()
+    }
+
+    /// Stops the process with `SIGTSTP`, with the terminal restored until it
+    /// is resumed
+    ///
+    /// The process isn't stopped on platforms other than Unix.
+    fn suspend(&mut self) -> IoResult {
+        self.flush()?;
+        let raw = self.is_raw();
+        self.disable_raw_mode()?;
+        #[cfg(unix)]
+        raise(SIGTSTP)?;
+        if raw {
+            self.enable_raw_mode()?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, text: &str) -> IoResult {
+        io::stdout().write_all(text.as_bytes())
+    }
+
+    /// Writes the message to the standard error, after what was written so
+    /// far to the standard output
+    fn write_error(&mut self, text: &str) -> IoResult {
+        io::stdout().flush()?;
+        let mut stderr = io::stderr();
+        stderr.write_all(text.as_bytes())?;
+        stderr.flush()
+    }
+}
+
+impl Drop for CrosstermTerminal {
+    fn drop(&mut self) {
+        self.flush().unwrap_or_default();
+        self.disable_raw_mode().unwrap_or_default();
+    }
+}
+
+/// Checks if colors are allowed by the environment
+///
+/// They are disabled if `NO_COLOR` is set to a non-empty value, see
+/// <https://no-color.org>, or in dumb rendering.
+pub fn detect_color(rendering: Rendering) -> bool {
+    rendering == Rendering::Full && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+}
+
+/// Checks if the value of `INSIDE_EMACS` is the one of a shell buffer
+///
+/// Emacs sets it to `t` before version 25, and to `<version>,comint` since.
+fn is_comint(inside: &str) -> bool {
+    inside == "t" || inside.ends_with(",comint")
+}
+
+/// Get the key pressed in an event, if it is a key press
+fn pressed_key(event: &Event) -> Option<Key> {
+    match event {
+        Event::Key(key) if key.kind != KeyEventKind::Release => Some(Key::from(*key)),
+        Event::FocusGained
+        | Event::FocusLost
+        | Event::Key(_)
+        | Event::Mouse(_)
+        | Event::Paste(_)
+        | Event::Resize(..) => None,
+    }
+}
+
+/// Installs, only once, a panic hook disabling the raw mode before the panic
+/// is reported by the previous hook
+///
+/// Only the panics of the thread that enabled the raw mode disable it, while
+/// it is enabled, so that a panicking worker thread doesn't leave the editor
+/// in cooked mode. The shared flag is cleared, so that the terminal enables
+/// the raw mode again if the panic is caught, e.g. in a handler.
+fn install_panic_hook() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let current = thread::current().id();
+            if RAW_MODE_THREAD
+                .lock()
+                .is_ok_and(|owner| *owner == Some(current))
+                && RAW_MODE.swap(false, Ordering::SeqCst)
+            {
+                terminal::disable_raw_mode().unwrap_or_default();
+            }
+            previous(info);
+        }));
+    });
+}
+
+#[cfg(test)]
+#[expect(clippy::inline_modules, reason = "tests")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comint() {
+        assert!(is_comint("t"));
+        assert!(is_comint("29.1,comint"));
+        assert!(!is_comint("vterm"));
+        assert!(!is_comint("29.1,eat"));
+        assert!(!is_comint("29.1,term:0.96"));
+    }
+}
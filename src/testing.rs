@@ -0,0 +1,158 @@
+//! Drives an application with scripted keys, to test it without a terminal
+
+use std::io;
+
+use crate::runner::{Action, Log};
+use crate::{App, Error, History, Key, KeyCode, VirtualTerminal};
+
+/// Runs an [`App`] on a [`VirtualTerminal`] with scripted keys
+///
+/// The keys are queued with [`Harness::type_str`] and [`Harness::key`], then
+/// processed by [`Harness::run`] or [`Harness::readline`]. The submitted
+/// lines, the history and the screen can then be asserted on.
+pub struct Harness<S: Action, L: Log> {
+    /// Application under test
+    app: App<S, L>,
+    /// Lines submitted so far
+    submitted: Vec<String>,
+    /// Terminal on which the application runs
+    terminal: VirtualTerminal,
+}
+
+impl<S: Action, L: Log> Harness<S, L> {
+    /// Get the application under test, e.g. to change its bindings
+    pub const fn app(&mut self) -> &mut App<S, L> {
+        &mut self.app
+    }
+
+    /// Asserts that the history in use contains exactly the given lines, from
+    /// the oldest to the most recent
+    ///
+    /// # Panics
+    ///
+    /// Panics if the history contains other lines.
+    pub fn assert_history(&self, expected: &[&str]) {
+        let history: Vec<&str> = self.history().iter().map(String::as_str).collect();
+        assert_eq!(history, expected, "unexpected history");
+    }
+
+    /// Asserts that the rows displayed on the screen are the given ones,
+    /// without their trailing spaces
+    ///
+    /// The empty rows at the bottom of the screen are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the screen displays other rows.
+    pub fn assert_screen(&self, expected: &[&str]) {
+        let mut screen = self.terminal.screen();
+        while screen.last().is_some_and(String::is_empty) {
+            screen.pop();
+        }
+        assert_eq!(screen, expected, "unexpected screen");
+    }
+
+    /// Asserts that exactly the given lines were submitted, in this order
+    ///
+    /// # Panics
+    ///
+    /// Panics if other lines were submitted.
+    pub fn assert_submitted(&self, expected: &[&str]) {
+        assert_eq!(self.submitted(), expected, "unexpected submitted lines");
+    }
+
+    /// Get the history in use by the application
+    ///
+    /// The history can be changed through [`Harness::app`], e.g. to seed it
+    /// before the keys are processed.
+    #[must_use]
+    pub const fn history(&self) -> &History {
+        self.app.current_history()
+    }
+
+    /// Queues a key to be pressed, e.g. `KeyCode::Enter` or `Key::ctrl('c')`
+    pub fn key<K>(&mut self, key: K) -> &mut Self
+    where
+        K: Into<Key>,
+    {
+        self.terminal.push_key(key.into());
+        self
+    }
+
+    /// Creates a harness running the application on a terminal of 80 columns
+    /// and 24 rows
+    pub fn new(app: App<S, L>) -> Self {
+        Self::with_terminal(app, VirtualTerminal::default())
+    }
+
+    /// Reads a line with the given prompt, see [`App::readline`]
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of [`App::readline`], with an [`Error::EventRead`] if
+    /// no line was submitted before the queued keys ran out.
+    pub fn readline(&mut self, prompt: &str) -> Result<String, Error> {
+        let line = self.app.readline(prompt)?;
+        self.submitted.push(line.clone());
+        Ok(line)
+    }
+
+    /// Runs the application until the queued keys run out, see [`App::run`]
+    ///
+    /// Returns the exit code if the application stopped before.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`App::run`].
+    pub fn run(&mut self) -> Result<Option<i32>, Error> {
+        let submitted = &mut self.submitted;
+        let ran = self.app.run_wrapped(|interface, on_submit| {
+            submitted.push(interface.line().to_owned());
+            if let Some(action) = on_submit {
+                action(interface);
+            }
+        });
+        match ran {
+            Ok(code) => Ok(Some(code)),
+            Err(Error::EventRead(err)) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Get the rows displayed on the screen, see [`VirtualTerminal::screen`]
+    #[must_use]
+    pub fn screen(&self) -> Vec<String> {
+        self.terminal.screen()
+    }
+
+    /// Get the lines submitted so far, in this order
+    ///
+    /// The lines are the ones given to the handler of
+    /// [`App::on_submit`] or returned by [`App::readline`], after history
+    /// expansion.
+    #[must_use]
+    pub fn submitted(&self) -> &[String] {
+        &self.submitted
+    }
+
+    /// Get the terminal on which the application runs
+    #[must_use]
+    pub const fn terminal(&self) -> &VirtualTerminal {
+        &self.terminal
+    }
+
+    /// Queues the characters of the text to be typed, one key per character
+    pub fn type_str(&mut self, text: &str) -> &mut Self {
+        for ch in text.chars() {
+            self.terminal.push_key(Key::plain(KeyCode::Char(ch)));
+        }
+        self
+    }
+
+    /// Creates a harness running the application on the given terminal, e.g.
+    /// to choose its size
+    pub fn with_terminal(mut app: App<S, L>, terminal: VirtualTerminal) -> Self {
+        app.terminal(terminal.clone());
+        Self { app, submitted: Vec::new(), terminal }
+    }
+}
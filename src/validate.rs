@@ -0,0 +1,166 @@
+//! Decides whether the line is submitted when Enter is pressed
+
+use crate::highlight::{BRACKETS, string_end};
+
+/// Checks that the brackets and the quotes of the line are balanced
+///
+/// The line is incomplete while a bracket or a quote isn't closed, and invalid
+/// if a bracket is closed by another kind of bracket or was never opened. The
+/// brackets are ignored inside the quotes, where quotes can be escaped with a
+/// backslash.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[expect(clippy::exhaustive_structs, reason = "unit validator")]
+pub struct Balanced;
+
+impl Validator for Balanced {
+    fn validate(&self, line: &str) -> Validation {
+        let mut expected = vec![];
+        let mut chars = line.char_indices();
+        while let Some((_, ch)) = chars.next() {
+            if ch == '"' || ch == '\'' {
+                if string_end(&mut chars, ch).is_none() {
+                    return Validation::Incomplete;
+                }
+            } else if let Some(&(_, close)) = BRACKETS.iter().find(|(open, _)| *open == ch) {
+                expected.push(close);
+            } else if BRACKETS.iter().any(|(_, close)| *close == ch) {
+                match expected.pop() {
+                    Some(close) if close == ch => (),
+                    Some(close) => {
+                        return Validation::Invalid(format!("expected `{close}`, found `{ch}`"));
+                    }
+                    None => return Validation::Invalid(format!("unmatched `{ch}`")),
+                }
+            }
+        }
+        if expected.is_empty() {
+            Validation::Complete
+        } else {
+            Validation::Incomplete
+        }
+    }
+}
+
+/// Continues the lines ending with a backslash
+///
+/// The backslash and the newline are kept in the submitted line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[expect(clippy::exhaustive_structs, reason = "unit validator")]
+pub struct TrailingBackslash;
+
+impl Validator for TrailingBackslash {
+    fn validate(&self, line: &str) -> Validation {
+        if line.ends_with('\\') {
+            Validation::Incomplete
+        } else {
+            Validation::Complete
+        }
+    }
+}
+
+/// Result of the validation of a line, see [`Validator`]
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Validation {
+    /// The line is submitted
+    Complete,
+    /// The line goes on: a newline is inserted at the cursor, and the
+    /// following rows start with the continuation prompt, see
+    /// [`App::continuation_prompt`](crate::App::continuation_prompt)
+    Incomplete,
+    /// The line can't be submitted: the message is shown below it and the
+    /// editing goes on
+    Invalid(String),
+}
+
+/// Decides whether the line is submitted when Enter is pressed, or how the
+/// editing goes on
+///
+/// When the input is piped, the incomplete lines are joined with the next
+/// ones, and the invalid ones are submitted anyway.
+///
+/// It is implemented for the closures taking the line, and for the pairs of
+/// validators, the second one being used only if the first one says the line
+/// is complete.
+pub trait Validator {
+    /// Validates the whole line, which can contain newlines
+    fn validate(&self, line: &str) -> Validation;
+}
+
+impl<F: Fn(&str) -> Validation> Validator for F {
+    fn validate(&self, line: &str) -> Validation {
+        self(line)
+    }
+}
+
+impl<A: Validator, B: Validator> Validator for (A, B) {
+    /// Validates the line with the first validator, then the second one if it
+    /// is complete
+    fn validate(&self, line: &str) -> Validation {
+        let (first, second) = self;
+        match first.validate(line) {
+            Validation::Complete => second.validate(line),
+            validation @ (Validation::Incomplete | Validation::Invalid(_)) => validation,
+        }
+    }
+}
+
+#[cfg(test)]
+#[expect(clippy::inline_modules, reason = "tests")]
+mod tests {
+    use super::*;
+
+    /// Shortcut for an invalid line
+    fn invalid(message: &str) -> Validation {
+        Validation::Invalid(message.to_owned())
+    }
+
+    #[test]
+    fn balanced() {
+        assert_eq!(Balanced.validate(""), Validation::Complete);
+        assert_eq!(Balanced.validate("f(a[1], {b})"), Validation::Complete);
+        assert_eq!(Balanced.validate("f(a[1"), Validation::Incomplete);
+        assert_eq!(Balanced.validate("f(a[1\n]"), Validation::Incomplete);
+        assert_eq!(Balanced.validate("f(a]"), invalid("expected `)`, found `]`"));
+        assert_eq!(Balanced.validate("a)"), invalid("unmatched `)`"));
+        assert_eq!(Balanced.validate("f(a)}"), invalid("unmatched `}`"));
+    }
+
+    #[test]
+    fn pair() {
+        let validator = (Balanced, TrailingBackslash);
+        assert_eq!(validator.validate("ls \\"), Validation::Incomplete);
+        assert_eq!(validator.validate("ls \\\n-l"), Validation::Complete);
+        assert_eq!(validator.validate("(ls \\"), Validation::Incomplete);
+        assert_eq!(validator.validate("ls) \\"), invalid("unmatched `)`"));
+        let closure = |line: &str| {
+            if line.contains("rm") {
+                invalid("forbidden")
+            } else {
+                Validation::Complete
+            }
+        };
+        let custom = (Balanced, closure);
+        assert_eq!(custom.validate("(rm"), Validation::Incomplete);
+        assert_eq!(custom.validate("(rm)"), invalid("forbidden"));
+        assert_eq!(custom.validate("(ls)"), Validation::Complete);
+    }
+
+    #[test]
+    fn quotes() {
+        assert_eq!(Balanced.validate("echo ')' \"]\""), Validation::Complete);
+        assert_eq!(Balanced.validate("echo '("), Validation::Incomplete);
+        assert_eq!(Balanced.validate("echo \"a"), Validation::Incomplete);
+        assert_eq!(Balanced.validate(r#"echo "a\"b" )"#), invalid("unmatched `)`"));
+        assert_eq!(Balanced.validate(r#"echo "a\" )"#), Validation::Incomplete);
+        assert_eq!(Balanced.validate(r"echo 'it\'s'"), Validation::Complete);
+        assert_eq!(Balanced.validate(r#"echo "a\\" )"#), invalid("unmatched `)`"));
+    }
+
+    #[test]
+    fn trailing_backslash() {
+        assert_eq!(TrailingBackslash.validate("ls"), Validation::Complete);
+        assert_eq!(TrailingBackslash.validate("ls \\"), Validation::Incomplete);
+        assert_eq!(TrailingBackslash.validate("ls \\ -l"), Validation::Complete);
+    }
+}
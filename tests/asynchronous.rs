@@ -0,0 +1,123 @@
+//! Drives the asynchronous runners on a virtual terminal
+
+use core::future::{self, Future};
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use clinput::{App, AppInterface, Error, KeyCode, LogEvent, VirtualTerminal};
+
+type Editor = App<fn(&mut AppInterface<'_>), fn(&LogEvent)>;
+
+/// Polls a future until it completes, spinning while it is pending
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut running = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = running.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+/// Completes on the given poll, to cancel the runner once it awaits
+async fn cancel_on(polls: usize) {
+    let mut count = 0usize;
+    future::poll_fn(|_| {
+        count = count.saturating_add(1);
+        if count >= polls {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await;
+}
+
+/// Asserts the non-empty rows at the top of the screen
+fn assert_screen(terminal: &VirtualTerminal, expected: &[&str]) {
+    let mut screen = terminal.screen();
+    while screen.last().is_some_and(String::is_empty) {
+        screen.pop();
+    }
+    assert_eq!(screen, expected);
+}
+
+/// Creates an application drawn on a new virtual terminal
+fn editor() -> (Editor, VirtualTerminal) {
+    let terminal = VirtualTerminal::new(40, 10);
+    let mut app = Editor::new();
+    app.terminal(terminal.clone());
+    (app, terminal)
+}
+
+/// Types a line on the terminal, followed by Enter if it is submitted
+fn type_line(terminal: &VirtualTerminal, line: &str, submit: bool) {
+    for character in line.chars() {
+        terminal.push_key(KeyCode::Char(character).into());
+    }
+    if submit {
+        terminal.push_key(KeyCode::Enter.into());
+    }
+}
+
+#[test]
+fn readline() {
+    let (mut app, terminal) = editor();
+    type_line(&terminal, "hello", true);
+    let line = block_on(app.readline_async(">>> ", future::pending::<()>()));
+    assert_eq!(line.unwrap(), "hello");
+    assert!(!terminal.is_raw_mode());
+}
+
+#[test]
+fn readline_cancelled() {
+    let (mut app, terminal) = editor();
+    type_line(&terminal, "draft", true);
+    let cancelled = block_on(app.readline_async(">>> ", future::ready(())));
+    assert!(matches!(cancelled.unwrap_err(), Error::Cancelled));
+    assert!(!terminal.is_raw_mode());
+    let line = block_on(app.readline_async(">>> ", future::pending::<()>()));
+    assert_eq!(line.unwrap(), "draft");
+}
+
+#[test]
+fn run() {
+    let (mut app, terminal) = editor();
+    type_line(&terminal, "first", true);
+    type_line(&terminal, "quit", true);
+    let lines = Rc::new(RefCell::new(Vec::new()));
+    let handled = Rc::clone(&lines);
+    let code = block_on(app.run_async(
+        async move |interface: &mut AppInterface<'_>| {
+            future::ready(()).await;
+            let line = interface.line().to_owned();
+            if line == "quit" {
+                interface.exit_with_code(3i32);
+            }
+            handled.borrow_mut().push(line);
+        },
+        future::pending::<()>(),
+    ));
+    assert_eq!(code.unwrap(), 3i32);
+    assert_eq!(*lines.borrow(), ["first", "quit"]);
+    assert_screen(&terminal, &[">>> first", ">>> quit"]);
+    assert!(!terminal.is_raw_mode());
+}
+
+#[test]
+fn run_cancelled() {
+    let (mut app, terminal) = editor();
+    type_line(&terminal, "wait", true);
+    type_line(&terminal, "draft", false);
+    let code = block_on(
+        app.run_async(async |_: &mut AppInterface<'_>| future::pending::<()>().await, cancel_on(2)),
+    );
+    assert!(matches!(code.unwrap_err(), Error::Cancelled));
+    assert_screen(&terminal, &[">>> wait"]);
+    assert!(!terminal.is_raw_mode());
+    type_line(&terminal, "", true);
+    let line = block_on(app.readline_async(">>> ", future::pending::<()>()));
+    assert_eq!(line.unwrap(), "draft");
+}
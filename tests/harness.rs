@@ -0,0 +1,161 @@
+//! Drives applications with the testing harness
+
+use clinput::testing::Harness;
+use clinput::{App, AppInterface, Balanced, Command, Key, KeyCode, LogEvent};
+
+type Editor = App<fn(&mut AppInterface<'_>), fn(&LogEvent)>;
+
+/// Prints the submitted line in upper case
+fn shout(interface: &mut AppInterface<'_>) {
+    let line = interface.line().to_uppercase();
+    interface.println(&line).unwrap();
+}
+
+#[test]
+fn history() {
+    let mut app = Editor::new();
+    app.history_expansion(true);
+    let mut harness = Harness::new(app);
+    harness
+        .type_str("ls")
+        .key(KeyCode::Enter)
+        .type_str("cd /")
+        .key(KeyCode::Enter)
+        .key(KeyCode::Up)
+        .key(KeyCode::Up)
+        .key(KeyCode::Enter)
+        .type_str("!!")
+        .key(KeyCode::Enter);
+    assert_eq!(harness.run().unwrap(), None);
+    harness.assert_submitted(&["ls", "cd /", "ls", "ls"]);
+    harness.assert_history(&["ls", "cd /", "ls", "ls"]);
+}
+
+#[test]
+fn validation() {
+    let mut app = Editor::new();
+    app.validator(Balanced);
+    let mut harness = Harness::new(app);
+    harness
+        .type_str("(1")
+        .key(KeyCode::Enter)
+        .type_str("]")
+        .key(KeyCode::Enter);
+    assert_eq!(harness.run().unwrap(), None);
+    harness.assert_screen(&[">>> (1", "... ]", "expected `)`, found `]`"]);
+    harness
+        .key(KeyCode::Backspace)
+        .type_str(")")
+        .key(KeyCode::Enter);
+    assert_eq!(harness.run().unwrap(), None);
+    harness.assert_submitted(&["(1\n)"]);
+    harness.assert_screen(&[">>> (1", "... )", ">>>"]);
+}
+
+#[test]
+fn recursive_commands() {
+    let mut app = Editor::new();
+    app.define_command(
+        "again",
+        Box::new(|interface: &mut AppInterface<'_>| {
+            interface.insert("x");
+            interface.run_command("again");
+        }),
+    );
+    app.define_command(
+        "ping",
+        Box::new(|interface: &mut AppInterface<'_>| {
+            interface.insert("a");
+            interface.run_command("pong");
+        }),
+    );
+    app.define_command(
+        "pong",
+        Box::new(|interface: &mut AppInterface<'_>| {
+            interface.insert("b");
+            interface.run_command("ping");
+        }),
+    );
+    app.bind_named(Key::ctrl('g'), "again").unwrap();
+    app.bind_named(Key::ctrl('o'), "ping").unwrap();
+    let mut harness = Harness::new(app);
+    harness
+        .key(Key::ctrl('g'))
+        .key(Key::ctrl('o'))
+        .key(Key::ctrl('g'))
+        .key(KeyCode::Enter);
+    assert_eq!(harness.run().unwrap(), None);
+    harness.assert_submitted(&["xabx"]);
+}
+
+#[test]
+fn sequence_timeout() {
+    let mut app = Editor::new();
+    let sequence = vec![KeyCode::Char('a').into(), KeyCode::Char('b').into()];
+    app.bind_sequence(sequence.clone(), Command::KillLine)
+        .unwrap();
+    let mut harness = Harness::new(app);
+    harness
+        .type_str("cd")
+        .key(KeyCode::Home)
+        .type_str("abac")
+        .key(KeyCode::Home)
+        .type_str("xa");
+    assert_eq!(harness.run().unwrap(), None);
+    harness.assert_screen(&[">>> xaac"]);
+    harness.app().unbind_sequence(&sequence);
+    harness.type_str("b").key(KeyCode::Enter);
+    assert_eq!(harness.run().unwrap(), None);
+    harness.assert_submitted(&["xabac"]);
+}
+
+#[test]
+fn history_error() {
+    let mut app = Editor::new();
+    app.history_expansion(true);
+    let mut harness = Harness::new(app);
+    harness
+        .type_str("ls")
+        .key(KeyCode::Enter)
+        .type_str("echo !zz")
+        .key(KeyCode::Enter);
+    assert_eq!(harness.run().unwrap(), None);
+    harness.assert_screen(&[">>> ls", ">>> echo !zz", "!zz: event not found"]);
+    harness
+        .key(KeyCode::Backspace)
+        .key(KeyCode::Backspace)
+        .key(KeyCode::Backspace)
+        .type_str("!!")
+        .key(KeyCode::Enter);
+    assert_eq!(harness.run().unwrap(), None);
+    harness.assert_submitted(&["ls", "echo ls"]);
+    harness.assert_history(&["ls", "echo ls"]);
+}
+
+#[test]
+fn output() {
+    let mut app = Editor::new();
+    app.on_submit(shout);
+    let mut harness = Harness::new(app);
+    harness
+        .type_str("hello")
+        .key(KeyCode::Enter)
+        .type_str("bye");
+    assert_eq!(harness.run().unwrap(), None);
+    harness.assert_screen(&[">>> hello", "HELLO", ">>> bye"]);
+}
+
+#[test]
+fn readline() {
+    let mut harness = Harness::new(Editor::new());
+    harness
+        .type_str("world")
+        .key(KeyCode::Home)
+        .type_str("hello ")
+        .key(KeyCode::Enter);
+    assert_eq!(harness.readline("name> ").unwrap(), "hello world");
+    harness.key(Key::ctrl('c'));
+    harness.readline("name> ").unwrap_err();
+    harness.assert_submitted(&["hello world"]);
+    harness.assert_screen(&["name> hello world", "name> ^C"]);
+}
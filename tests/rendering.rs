@@ -0,0 +1,100 @@
+//! Checks how the applications are drawn on a virtual terminal
+
+use clinput::testing::Harness;
+use clinput::{App, AppInterface, Key, KeyCode, LogEvent, Rendering, VirtualTerminal};
+
+type Action = fn(&mut AppInterface<'_>);
+type Editor = App<Action, fn(&LogEvent)>;
+
+/// Runs the application until the queued keys run out
+fn run(harness: &mut Harness<Action, fn(&LogEvent)>) {
+    assert_eq!(harness.run().unwrap(), None);
+}
+
+#[test]
+fn clear_screen() {
+    let mut harness = Harness::new(Editor::new());
+    harness
+        .type_str("ls")
+        .key(KeyCode::Enter)
+        .type_str("pwd")
+        .key(Key::ctrl('l'));
+    run(&mut harness);
+    harness.assert_screen(&[">>> pwd"]);
+}
+
+#[test]
+fn dumb() {
+    let mut app = Editor::new();
+    app.rendering(Rendering::Dumb);
+    let mut harness = Harness::new(app);
+    harness
+        .type_str("hello")
+        .key(KeyCode::Backspace)
+        .key(KeyCode::Left)
+        .type_str("p")
+        .key(KeyCode::Enter)
+        .type_str("bye");
+    run(&mut harness);
+    harness.assert_screen(&[">>> helpl", ">>> bye"]);
+}
+
+#[test]
+fn history() {
+    let mut harness = Harness::new(Editor::new());
+    harness
+        .type_str("first")
+        .key(KeyCode::Enter)
+        .type_str("second line")
+        .key(KeyCode::Enter)
+        .type_str("draft")
+        .key(KeyCode::Up)
+        .key(KeyCode::Up);
+    run(&mut harness);
+    harness.assert_screen(&[">>> first", ">>> second line", ">>> first"]);
+}
+
+#[test]
+fn history_down() {
+    let mut harness = Harness::new(Editor::new());
+    harness
+        .type_str("first")
+        .key(KeyCode::Enter)
+        .type_str("second line")
+        .key(KeyCode::Enter)
+        .key(KeyCode::Up)
+        .key(KeyCode::Up)
+        .key(KeyCode::Down)
+        .key(KeyCode::Enter);
+    run(&mut harness);
+    harness.assert_submitted(&["first", "second line", "second line"]);
+    harness.assert_screen(&[">>> first", ">>> second line", ">>> second line", ">>>"]);
+}
+
+#[test]
+fn interrupt() {
+    let mut harness = Harness::new(Editor::new());
+    harness
+        .type_str("oops")
+        .key(KeyCode::Left)
+        .key(Key::ctrl('c'))
+        .type_str("ok");
+    run(&mut harness);
+    harness.assert_screen(&[">>> oops^C", ">>> ok"]);
+    harness.assert_submitted(&[]);
+}
+
+#[test]
+fn wrap() {
+    let mut harness = Harness::with_terminal(Editor::new(), VirtualTerminal::new(10, 5));
+    harness
+        .type_str("abcdefghijklmnop")
+        .key(KeyCode::Home)
+        .type_str("> ");
+    run(&mut harness);
+    harness.assert_screen(&[">>> > abcd", "efghijklmn", "op"]);
+    harness.key(KeyCode::Enter);
+    run(&mut harness);
+    harness.assert_submitted(&["> abcdefghijklmnop"]);
+    harness.assert_screen(&[">>> > abcd", "efghijklmn", "op", ">>>"]);
+}